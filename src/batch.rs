@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+// The same trim applied to a pile of files, each queued as its own export
+#[derive(Default)]
+pub struct Batch {
+  pub files: Vec<PathBuf>,
+  // Otherwise the current start/end is used as is
+  pub trim_ends: bool,
+  pub head_s: f64,
+  pub tail_s: f64,
+}
+
+impl Batch {
+  // Start/end for a file `duration_ms` long, None if nothing would be left of it
+  pub fn range(&self, duration_ms: i64, start: Option<i64>, end: Option<i64>) -> Option<(Option<i64>, Option<i64>)> {
+    let (start, end) = if self.trim_ends {
+      let head = (self.head_s * 1000.) as i64;
+      let tail = (self.tail_s * 1000.) as i64;
+      ((head > 0).then_some(head), (tail > 0).then_some(duration_ms - tail))
+    } else {
+      (start, end.map(|end| end.min(duration_ms)))
+    };
+    (end.unwrap_or(duration_ms) > start.unwrap_or(0)).then_some((start, end))
+  }
+}
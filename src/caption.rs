@@ -0,0 +1,110 @@
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Corner {
+  TopLeft,
+  TopRight,
+  BottomLeft,
+  BottomRight,
+}
+
+impl Corner {
+  pub const ALL: [Corner; 4] = [
+    Corner::TopLeft,
+    Corner::TopRight,
+    Corner::BottomLeft,
+    Corner::BottomRight,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Corner::TopLeft => "Top left",
+      Corner::TopRight => "Top right",
+      Corner::BottomLeft => "Bottom left",
+      Corner::BottomRight => "Bottom right",
+    }
+  }
+
+  fn position(&self) -> &'static str {
+    match self {
+      Corner::TopLeft => "x=16:y=16",
+      Corner::TopRight => "x=w-tw-16:y=16",
+      Corner::BottomLeft => "x=16:y=h-th-16",
+      Corner::BottomRight => "x=w-tw-16:y=h-th-16",
+    }
+  }
+}
+
+pub struct Caption {
+  pub text: String,
+  pub corner: Corner,
+  pub font_size: u32,
+  // Embed as a subtitle track instead of drawing onto the video
+  pub soft: bool,
+}
+
+impl Default for Caption {
+  fn default() -> Self {
+    Self {
+      text: String::new(),
+      corner: Corner::BottomRight,
+      font_size: 32,
+      soft: false,
+    }
+  }
+}
+
+// drawtext values get unescaped twice, once as an option value and once
+// more as part of the filtergraph
+fn escape(value: &str) -> String {
+  let escape_chars = |s: &str, special: &str| {
+    s.chars().fold(String::new(), |mut out, c| {
+      if special.contains(c) {
+        out.push('\\');
+      }
+      out.push(c);
+      out
+    })
+  };
+  escape_chars(&escape_chars(value, "\\':%"), "\\'[],;")
+}
+
+fn font() -> String {
+  let candidates: &[&str] = if cfg!(windows) {
+    &["C:/Windows/Fonts/arial.ttf", "C:/Windows/Fonts/segoeui.ttf"]
+  } else if cfg!(target_os = "macos") {
+    &["/System/Library/Fonts/Helvetica.ttc", "/Library/Fonts/Arial.ttf"]
+  } else {
+    &["/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", "/usr/share/fonts/TTF/DejaVuSans.ttf"]
+  };
+  match candidates.iter().find(|font| Path::new(font).exists()) {
+    Some(font) => format!("fontfile={}", escape(font)),
+    // Leave it to fontconfig
+    None => "font=Sans".to_owned(),
+  }
+}
+
+impl Caption {
+  pub fn is_empty(&self) -> bool {
+    self.text.trim().is_empty()
+  }
+
+  pub fn filter(&self) -> Option<String> {
+    if self.is_empty() {
+      return None;
+    }
+    Some(format!(
+      "drawtext={}:text={}:fontsize={}:fontcolor=white:borderw=2:bordercolor=black:{}",
+      font(),
+      escape(&self.text),
+      self.font_size,
+      self.corner.position(),
+    ))
+  }
+
+  // A single cue covering the whole clip
+  pub fn srt(&self, from_ms: i64, to_ms: i64) -> String {
+    let time = |ms| crate::format_ms(ms).replace('.', ",");
+    format!("1\n{} --> {}\n{}\n", time(from_ms), time(to_ms), self.text.trim())
+  }
+}
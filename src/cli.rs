@@ -0,0 +1,234 @@
+use std::{
+  ffi::OsString,
+  fs,
+  io::{self, Write},
+  path::PathBuf,
+  time::Duration,
+};
+
+use crate::{
+  absolute,
+  format::OutputFormat,
+  format_ms,
+  job::{Job, JobKind},
+  parse_ms,
+  probe,
+};
+
+pub const USAGE: &str = "usage: snip <video> [--start HH:MM:SS.mmm] [--end HH:MM:SS.mmm] --output <file> [--ffmpeg <path>]";
+
+// Trims without opening a window, for use in scripts
+pub struct Cli {
+  input: PathBuf,
+  output: PathBuf,
+  ffmpeg: PathBuf,
+  start: Option<i64>,
+  end: Option<i64>,
+}
+
+fn time_arg(flag: &str, value: Option<&OsString>) -> Result<i64, String> {
+  let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+  value.to_str()
+    .and_then(parse_ms)
+    .ok_or_else(|| format!("{}: `{}` isn't a HH:MM:SS[.mmm] timestamp", flag, value.to_string_lossy()))
+}
+
+impl Cli {
+  // None means none of the headless flags were given and the gui should start
+  // Paths come straight from the OsStrings, since they don't have to be valid unicode
+  pub fn parse(args: &[OsString]) -> Result<Option<Cli>, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut ffmpeg = None;
+    let mut start = None;
+    let mut end = None;
+    // Only an error once it's clear this is a headless run, the gui takes stray arguments fine
+    let mut unexpected = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+      match arg.to_str() {
+        Some("--start") => start = Some(time_arg("--start", args.next())?),
+        Some("--end") => end = Some(time_arg("--end", args.next())?),
+        Some("--output") => output = Some(PathBuf::from(args.next().ok_or("--output needs a value")?)),
+        Some("--ffmpeg") => ffmpeg = Some(PathBuf::from(args.next().ok_or("--ffmpeg needs a value")?)),
+        Some(flag) if flag.starts_with("--") => {
+          unexpected.get_or_insert(format!("unknown flag `{}`", flag));
+        },
+        _ if input.is_none() => input = Some(PathBuf::from(arg)),
+        _ => {
+          unexpected.get_or_insert(format!("unexpected argument `{}`", arg.to_string_lossy()));
+        },
+      }
+    }
+    if output.is_none() && start.is_none() && end.is_none() {
+      return Ok(None);
+    }
+    if let Some(err) = unexpected {
+      return Err(err);
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+      if start > end {
+        return Err("--start is after --end".to_owned());
+      }
+    }
+    Ok(Some(Cli {
+      input: absolute(input.ok_or("no input video given")?),
+      output: absolute(output.ok_or("--output is required")?),
+      ffmpeg: ffmpeg.unwrap_or_else(|| PathBuf::from("ffmpeg")),
+      start,
+      end,
+    }))
+  }
+
+  fn args(&self, format: OutputFormat) -> Vec<OsString> {
+    let mut args = vec![];
+    if let Some(start) = self.start {
+      args.push("-ss".into());
+      args.push(format_ms(start).into());
+    }
+    if let Some(end) = self.end {
+      args.push("-to".into());
+      args.push(format_ms(end).into());
+    }
+    args.push("-i".into());
+    args.push(self.input.as_os_str().into());
+    if format == OutputFormat::Gif {
+      args.push("-filter_complex".into());
+      args.push("[0:v]split[a][b];[a]palettegen[p];[b][p]paletteuse".into());
+      args.push("-an".into());
+    } else {
+      if format.is_audio_only() {
+        args.push("-vn".into());
+      } else {
+        args.push("-map".into());
+        args.push("0".into());
+      }
+      if let Some(codec) = format.video_codec() {
+        args.push("-c:v".into());
+        args.push(codec.into());
+      }
+      if let Some(codec) = format.audio_codec() {
+        args.push("-c:a".into());
+        args.push(codec.into());
+      }
+    }
+    args.push(self.output.as_os_str().into());
+    args
+  }
+
+  // Returns the process exit code
+  pub fn run(self) -> i32 {
+    let extension = self.output.extension()
+      .map(|ext| ext.to_string_lossy().to_lowercase())
+      .unwrap_or_default();
+    let Some(format) = OutputFormat::ALL.into_iter().find(|f| f.extension() == extension) else {
+      eprintln!("can't tell the output format from `{}`", self.output.display());
+      return 2;
+    };
+
+    let end = match self.end {
+      Some(end) => end,
+      None => match probe::probe_info(&self.ffmpeg, &self.input).and_then(|info| info.duration) {
+        Some(duration) => (duration * 1000.) as i64,
+        None => {
+          eprintln!("couldn't probe the duration of `{}`", self.input.display());
+          return 1;
+        },
+      },
+    };
+    let duration = (end - self.start.unwrap_or(0)) as f64;
+
+    if self.output.exists() {
+      if let Err(err) = fs::remove_file(&self.output) {
+        eprintln!("couldn't replace `{}`: {}", self.output.display(), err);
+        return 1;
+      }
+    }
+    let mut job = Job::new();
+    if let Err(err) = job.start(&self.ffmpeg, vec![self.args(format)], JobKind::of(format), duration, vec![], || {}) {
+      eprintln!("failed to run ffmpeg at `{}`: {}", self.ffmpeg.display(), err);
+      return 1;
+    }
+    while job.in_progress() {
+      if job.is_indeterminate() {
+        print!("\rworking...");
+      } else {
+        print!("\r{:.2}%", job.progress().clamp(0., 1.) * 100.);
+      }
+      let _ = io::stdout().flush();
+      std::thread::sleep(Duration::from_millis(250));
+    }
+    if job.succeeded() {
+      println!("\rsaved {}", self.output.display());
+      0
+    } else {
+      println!();
+      match job.error() {
+        Some(err) => {
+          for line in &err.log {
+            eprintln!("{}", line);
+          }
+          eprintln!("{}", err.summary);
+        },
+        None => eprintln!("ffmpeg failed"),
+      }
+      1
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn os(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+  }
+
+  #[test]
+  fn no_headless_flags_starts_the_gui() {
+    assert!(matches!(Cli::parse(&os(&["clip.mp4", "stray"])), Ok(None)));
+  }
+
+  #[test]
+  fn parses_a_headless_run() {
+    let cli = Cli::parse(&os(&["in.mp4", "--start", "00:00:05", "--end", "00:00:10.5", "--output", "out.mp4"]))
+      .unwrap()
+      .unwrap();
+    assert_eq!(cli.start, Some(5000));
+    assert_eq!(cli.end, Some(10500));
+    assert!(cli.input.ends_with("in.mp4") && cli.input.is_absolute());
+    assert!(cli.output.ends_with("out.mp4") && cli.output.is_absolute());
+  }
+
+  #[test]
+  fn start_after_end_is_an_error() {
+    assert!(Cli::parse(&os(&["in.mp4", "--start", "00:00:10", "--end", "00:00:05", "--output", "out.mp4"])).is_err());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn keeps_paths_that_arent_unicode() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let input = OsString::from_vec(b"/tmp/clip-\xff.mp4".to_vec());
+    let output = OsString::from_vec(b"/tmp/out-\xfe.mp4".to_vec());
+    let cli = Cli::parse(&[input.clone(), "--output".into(), output.clone()]).unwrap().unwrap();
+    assert_eq!(cli.input.as_os_str().as_bytes(), input.as_bytes());
+    assert_eq!(cli.output.as_os_str().as_bytes(), output.as_bytes());
+    assert!(matches!(Cli::parse(&[input]), Ok(None)));
+  }
+
+  #[test]
+  fn paths_stay_single_arguments() {
+    let input = "/tmp/my clips/it's \"ready\" – déjà vu.mp4";
+    let output = "/tmp/my clips/out put.webm";
+    let cli = Cli::parse(&os(&[input, "--output", output])).unwrap().unwrap();
+    for format in [OutputFormat::Mp4, OutputFormat::Gif] {
+      let args = cli.args(format);
+      let at = args.iter().position(|arg| arg == "-i").unwrap();
+      assert_eq!(args[at + 1], input);
+      assert_eq!(args.last().unwrap(), output);
+    }
+  }
+}
@@ -0,0 +1,69 @@
+use std::{ffi::OsString, path::{Path, PathBuf}};
+
+use crate::{format::OutputFormat, format_ms};
+
+// One trimmed piece of a concatenated reel
+pub struct Clip {
+  pub path: PathBuf,
+  pub start: Option<i64>,
+  pub end: Option<i64>,
+  pub duration_ms: i64,
+  pub size: Option<(u32, u32)>,
+  pub has_audio: bool,
+}
+
+impl Clip {
+  pub fn length(&self) -> i64 {
+    (self.end.unwrap_or(self.duration_ms) - self.start.unwrap_or(0)).max(0)
+  }
+}
+
+// Every clip is scaled and padded to the first clip's resolution, since
+// the concat filter needs matching frame sizes
+pub fn concat_args(clips: &[Clip], format: OutputFormat, output: &Path) -> Vec<OsString> {
+  let (w, h) = clips.iter().find_map(|clip| clip.size).unwrap_or((1920, 1080));
+  let mut args = vec![];
+  for clip in clips {
+    if let Some(start) = clip.start {
+      args.push("-ss".into());
+      args.push(format_ms(start).into());
+    }
+    if let Some(end) = clip.end {
+      args.push("-to".into());
+      args.push(format_ms(end).into());
+    }
+    args.push("-i".into());
+    args.push(clip.path.as_os_str().into());
+  }
+
+  let mut filters = vec![];
+  let mut inputs = String::new();
+  for (i, clip) in clips.iter().enumerate() {
+    filters.push(format!(
+      "[{0}:v]scale={1}:{2}:force_original_aspect_ratio=decrease,pad={1}:{2}:(ow-iw)/2:(oh-ih)/2,setsar=1[v{0}]",
+      i, w, h,
+    ));
+    // concat needs audio from every clip, so silent ones get generated silence
+    if clip.has_audio {
+      filters.push(format!("[{0}:a:0]aresample=48000[a{0}]", i));
+    } else {
+      filters.push(format!("anullsrc=r=48000:cl=stereo,atrim=duration={1}[a{0}]", i, clip.length() as f64 / 1000.));
+    }
+    inputs.push_str(&format!("[v{0}][a{0}]", i));
+  }
+  filters.push(format!("{}concat=n={}:v=1:a=1[v][a]", inputs, clips.len()));
+
+  args.push("-filter_complex".into());
+  args.push(filters.join(";").into());
+  args.extend(["-map", "[v]", "-map", "[a]"].map(OsString::from));
+  if let Some(codec) = format.video_codec() {
+    args.push("-c:v".into());
+    args.push(codec.into());
+  }
+  if let Some(codec) = format.audio_codec() {
+    args.push("-c:a".into());
+    args.push(codec.into());
+  }
+  args.push(output.as_os_str().into());
+  args
+}
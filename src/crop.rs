@@ -0,0 +1,72 @@
+use egui::{Color32, Pos2, Rect, Rounding, Sense, Stroke, Ui, Vec2};
+
+#[derive(Default)]
+pub struct Crop {
+  pub editing: bool,
+  // In source pixel coordinates
+  region: Option<Rect>,
+  drag_from: Option<Pos2>,
+}
+
+impl Crop {
+  pub fn reset(&mut self) {
+    self.region = None;
+    self.drag_from = None;
+  }
+
+  // Encoders want even dimensions, so width and height are rounded down
+  pub fn bounds(&self) -> Option<(u32, u32, u32, u32)> {
+    let region = self.region?;
+    let w = region.width().round() as u32 & !1;
+    let h = region.height().round() as u32 & !1;
+    if w < 2 || h < 2 {
+      return None;
+    }
+    Some((w, h, region.left().round() as u32, region.top().round() as u32))
+  }
+
+  pub fn filter(&self) -> Option<String> {
+    self.bounds().map(|(w, h, x, y)| format!("crop={}:{}:{}:{}", w, h, x, y))
+  }
+
+  // `frame` is where the preview is drawn, `source` is the real video resolution
+  pub fn ui(&mut self, ui: &mut Ui, frame: Rect, source: Vec2) {
+    if frame.width() <= 0. || frame.height() <= 0. {
+      return;
+    }
+    let scale = source / frame.size();
+    let to_source = |pos: Pos2| ((frame.clamp(pos) - frame.min) * scale).to_pos2();
+    let to_screen = |pos: Pos2| frame.min + pos.to_vec2() / scale;
+
+    if self.editing {
+      let response = ui.interact(frame, ui.id().with("crop"), Sense::drag());
+      if response.drag_started() {
+        self.drag_from = response.interact_pointer_pos().map(to_source);
+      }
+      if let (Some(from), Some(pos)) = (self.drag_from, response.interact_pointer_pos()) {
+        if response.dragged() {
+          self.region = Some(Rect::from_two_pos(from, to_source(pos)));
+        }
+      }
+      if response.drag_released() {
+        self.drag_from = None;
+      }
+    }
+
+    let Some(region) = self.region else {
+      return;
+    };
+    let region = Rect::from_min_max(to_screen(region.min), to_screen(region.max));
+    let painter = ui.painter_at(frame);
+    let shade = Color32::from_black_alpha(140);
+    for outside in [
+      Rect::from_x_y_ranges(frame.x_range(), frame.top()..=region.top()),
+      Rect::from_x_y_ranges(frame.x_range(), region.bottom()..=frame.bottom()),
+      Rect::from_x_y_ranges(frame.left()..=region.left(), region.y_range()),
+      Rect::from_x_y_ranges(region.right()..=frame.right(), region.y_range()),
+    ] {
+      painter.rect_filled(outside, Rounding::ZERO, shade);
+    }
+    painter.rect_stroke(region, Rounding::ZERO, Stroke::new(1.5, Color32::from_rgb(80, 160, 255)));
+  }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum OutputFormat {
+  Mp4,
+  WebM,
+  Mkv,
+  Mov,
+  Gif,
+  Mp3,
+  Wav,
+}
+
+impl OutputFormat {
+  pub const ALL: [OutputFormat; 7] = [
+    OutputFormat::Mp4,
+    OutputFormat::WebM,
+    OutputFormat::Mkv,
+    OutputFormat::Mov,
+    OutputFormat::Gif,
+    OutputFormat::Mp3,
+    OutputFormat::Wav,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      OutputFormat::Mp4 => "MP4",
+      OutputFormat::WebM => "WebM",
+      OutputFormat::Mkv => "MKV",
+      OutputFormat::Mov => "MOV",
+      OutputFormat::Gif => "GIF",
+      OutputFormat::Mp3 => "MP3",
+      OutputFormat::Wav => "WAV",
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      OutputFormat::Mp4 => "mp4",
+      OutputFormat::WebM => "webm",
+      OutputFormat::Mkv => "mkv",
+      OutputFormat::Mov => "mov",
+      OutputFormat::Gif => "gif",
+      OutputFormat::Mp3 => "mp3",
+      OutputFormat::Wav => "wav",
+    }
+  }
+
+  pub fn has_audio(&self) -> bool {
+    !matches!(self, OutputFormat::Gif)
+  }
+
+  pub fn is_audio_only(&self) -> bool {
+    matches!(self, OutputFormat::Mp3 | OutputFormat::Wav)
+  }
+
+  // Regular video containers, which can take stream copies and bitrate targets
+  pub fn is_video(&self) -> bool {
+    matches!(self, OutputFormat::Mp4 | OutputFormat::WebM | OutputFormat::Mkv | OutputFormat::Mov)
+  }
+
+  pub fn video_codec(&self) -> Option<&'static str> {
+    match self {
+      OutputFormat::WebM => Some("libvpx-vp9"),
+      OutputFormat::Gif => Some("gif"),
+      OutputFormat::Mp3 | OutputFormat::Wav => None,
+      _ => Some("libx264"),
+    }
+  }
+
+  // Containers that can hold a soft subtitle track
+  pub fn subtitle_codec(&self) -> Option<&'static str> {
+    match self {
+      OutputFormat::Mp4 | OutputFormat::Mov => Some("mov_text"),
+      OutputFormat::Mkv => Some("srt"),
+      OutputFormat::WebM => Some("webvtt"),
+      _ => None,
+    }
+  }
+
+  pub fn audio_codec(&self) -> Option<&'static str> {
+    match self {
+      OutputFormat::WebM => Some("libopus"),
+      OutputFormat::Gif => None,
+      OutputFormat::Mp3 => Some("libmp3lame"),
+      OutputFormat::Wav => Some("pcm_s16le"),
+      _ => Some("aac"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum AudioCodec {
+  Default,
+  Aac,
+  Opus,
+  Mp3,
+  Copy,
+}
+
+impl AudioCodec {
+  pub const ALL: [AudioCodec; 5] = [
+    AudioCodec::Default,
+    AudioCodec::Aac,
+    AudioCodec::Opus,
+    AudioCodec::Mp3,
+    AudioCodec::Copy,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      AudioCodec::Default => "Format default",
+      AudioCodec::Aac => "AAC",
+      AudioCodec::Opus => "Opus",
+      AudioCodec::Mp3 => "MP3",
+      AudioCodec::Copy => "Copy",
+    }
+  }
+
+  pub fn encoder(&self) -> Option<&'static str> {
+    match self {
+      AudioCodec::Default => None,
+      AudioCodec::Aac => Some("aac"),
+      AudioCodec::Opus => Some("libopus"),
+      AudioCodec::Mp3 => Some("libmp3lame"),
+      AudioCodec::Copy => Some("copy"),
+    }
+  }
+
+  // Whether `format` can hold this codec, copies depend on the source so they always pass
+  pub fn fits(&self, format: OutputFormat) -> bool {
+    matches!(
+      (self, format),
+      (AudioCodec::Default | AudioCodec::Copy, _)
+        | (_, OutputFormat::Mkv)
+        | (AudioCodec::Aac | AudioCodec::Mp3, OutputFormat::Mp4 | OutputFormat::Mov)
+        | (AudioCodec::Opus, OutputFormat::WebM)
+        | (AudioCodec::Mp3, OutputFormat::Mp3)
+    )
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Resolution {
+  Original,
+  P1080,
+  P720,
+  P480,
+}
+
+impl Resolution {
+  pub const ALL: [Resolution; 4] = [
+    Resolution::Original,
+    Resolution::P1080,
+    Resolution::P720,
+    Resolution::P480,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Resolution::Original => "Original",
+      Resolution::P1080 => "1080p",
+      Resolution::P720 => "720p",
+      Resolution::P480 => "480p",
+    }
+  }
+
+  pub fn height(&self) -> Option<u32> {
+    match self {
+      Resolution::Original => None,
+      Resolution::P1080 => Some(1080),
+      Resolution::P720 => Some(720),
+      Resolution::P480 => Some(480),
+    }
+  }
+
+  // The 16:9 frame used when letterboxing
+  pub fn size(&self) -> Option<(u32, u32)> {
+    match self {
+      Resolution::Original => None,
+      Resolution::P1080 => Some((1920, 1080)),
+      Resolution::P720 => Some((1280, 720)),
+      Resolution::P480 => Some((854, 480)),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rotation {
+  None,
+  Clockwise,
+  CounterClockwise,
+  UpsideDown,
+  FlipHorizontal,
+}
+
+impl Rotation {
+  pub const ALL: [Rotation; 5] = [
+    Rotation::None,
+    Rotation::Clockwise,
+    Rotation::CounterClockwise,
+    Rotation::UpsideDown,
+    Rotation::FlipHorizontal,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Rotation::None => "None",
+      Rotation::Clockwise => "90° CW",
+      Rotation::CounterClockwise => "90° CCW",
+      Rotation::UpsideDown => "180°",
+      Rotation::FlipHorizontal => "Flip horizontal",
+    }
+  }
+
+  pub fn filter(&self) -> Option<&'static str> {
+    match self {
+      Rotation::None => None,
+      Rotation::Clockwise => Some("transpose=clock"),
+      Rotation::CounterClockwise => Some("transpose=cclock"),
+      Rotation::UpsideDown => Some("hflip,vflip"),
+      Rotation::FlipHorizontal => Some("hflip"),
+    }
+  }
+}
+
+pub const VIDEO_EXTENSIONS: [&str; 12] = [
+  "mp4", "mkv", "webm", "mov", "avi", "flv", "wmv", "m4v", "ts", "mpg", "mpeg", "gif",
+];
+
+pub const X264_PRESETS: [&str; 9] = [
+  "ultrafast",
+  "superfast",
+  "veryfast",
+  "faster",
+  "fast",
+  "medium",
+  "slow",
+  "slower",
+  "veryslow",
+];
+
+pub const H264_ENCODERS: [&str; 4] = [
+  "libx264",
+  "h264_nvenc",
+  "h264_qsv",
+  "h264_videotoolbox",
+];
+
+pub fn is_hardware_encoder(encoder: &str) -> bool {
+  encoder != "libx264" && H264_ENCODERS.contains(&encoder)
+}
+
+pub fn crf_hint(crf: u8) -> &'static str {
+  match crf {
+    0 => "lossless",
+    1..=17 => "visually lossless",
+    18..=23 => "high quality",
+    24..=28 => "good quality",
+    29..=35 => "low quality",
+    _ => "very low quality",
+  }
+}
@@ -0,0 +1,123 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use egui::{ColorImage, Context, TextureHandle, TextureOptions, Ui, Vec2};
+
+const WIDTH: usize = 320;
+const FPS: u64 = 10;
+// Keeps the preview quick to make and the decoded frames small
+const MAX_LENGTH_MS: i64 = 15000;
+
+type Frames = Result<Vec<ColorImage>, String>;
+
+#[derive(Default)]
+pub struct GifPreview {
+  loading: bool,
+  loaded: Arc<Mutex<Option<Frames>>>,
+  frames: Vec<TextureHandle>,
+  error: Option<String>,
+  shown: Option<Instant>,
+  path: Option<PathBuf>,
+}
+
+fn preview_path(dir: &Path) -> PathBuf {
+  dir.join(format!("snip-preview-{}.gif", std::process::id()))
+}
+
+// The gif is written to disk and the same frames are piped out as raw RGBA to show inline
+fn render(ffmpeg: &Path, file: &Path, gif: &Path, start: i64, length: i64, [w, h]: [usize; 2]) -> Frames {
+  let graph = format!(
+    "[0:v]fps={},scale={}:{},split=3[a][b][raw];[a]palettegen[p];[b][p]paletteuse[gif]",
+    FPS, w, h,
+  );
+  let output = Command::new(ffmpeg)
+    .args(["-v", "error", "-y", "-ss", &format!("{}", start as f64 / 1000.), "-t", &format!("{}", length as f64 / 1000.), "-i"])
+    .arg(file)
+    .args(["-filter_complex", &graph, "-map", "[gif]"])
+    .arg(gif)
+    .args(["-map", "[raw]", "-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|err| err.to_string())?;
+  if !output.status.success() {
+    let log = String::from_utf8_lossy(&output.stderr);
+    return Err(log.lines().last().unwrap_or("ffmpeg failed").to_owned());
+  }
+  Ok(output.stdout.chunks_exact(w * h * 4).map(|frame| ColorImage::from_rgba_unmultiplied([w, h], frame)).collect())
+}
+
+impl GifPreview {
+  pub fn is_loading(&self) -> bool {
+    self.loading
+  }
+
+  // The gif is written to `dir`
+  pub fn generate(&mut self, ctx: &Context, ffmpeg: &Path, file: &Path, dir: &Path, source: Vec2, (start, end): (i64, i64)) {
+    if self.loading || source.x <= 0. {
+      return;
+    }
+    self.loading = true;
+    self.error = None;
+    let height = ((WIDTH as f32 * source.y / source.x) as usize).max(2) & !1;
+    let length = (end - start).clamp(1, MAX_LENGTH_MS);
+    let ffmpeg = ffmpeg.to_path_buf();
+    let file = file.to_path_buf();
+    let gif = preview_path(dir);
+    self.path = Some(gif.clone());
+    let loaded = self.loaded.clone();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+      let frames = render(&ffmpeg, &file, &gif, start, length, [WIDTH, height]);
+      *loaded.lock().unwrap() = Some(frames);
+      ctx.request_repaint();
+    });
+  }
+
+  // A preview still being made is dropped along with the rest, and the gif
+  // is removed
+  pub fn clear(&mut self) {
+    *self = Self::default();
+  }
+
+  pub fn ui(&mut self, ui: &mut Ui) {
+    if let Some(frames) = self.loaded.lock().unwrap().take() {
+      self.loading = false;
+      match frames {
+        Ok(frames) => {
+          self.frames = frames.into_iter()
+            .enumerate()
+            .map(|(i, frame)| ui.ctx().load_texture(format!("gif-preview-{}", i), frame, TextureOptions::LINEAR))
+            .collect();
+          self.shown = Some(Instant::now());
+        },
+        Err(err) => self.error = Some(err),
+      }
+    }
+
+    if self.loading {
+      ui.label("making preview...");
+    }
+    if let Some(err) = &self.error {
+      ui.colored_label(egui::Color32::RED, format!("couldn't make a preview: {}", err));
+    }
+    if let (Some(shown), Some(path), false) = (self.shown, &self.path, self.frames.is_empty()) {
+      let frame = (shown.elapsed().as_millis() as u64 * FPS / 1000) as usize % self.frames.len();
+      ui.image(&self.frames[frame]);
+      ui.label(format!("saved to {} (first {}s at most)", path.display(), MAX_LENGTH_MS / 1000));
+      ui.ctx().request_repaint_after(Duration::from_millis(1000 / FPS));
+    }
+  }
+}
+
+impl Drop for GifPreview {
+  fn drop(&mut self) {
+    if let Some(path) = &self.path {
+      let _ = fs::remove_file(path);
+    }
+  }
+}
@@ -0,0 +1,41 @@
+const MAX_HISTORY: usize = 50;
+
+type Markers = (Option<i64>, Option<i64>);
+
+// Undo/redo for the start and end markers
+#[derive(Default)]
+pub struct History {
+  undo: Vec<Markers>,
+  redo: Vec<Markers>,
+  current: Markers,
+}
+
+impl History {
+  // Called every frame. Changes are only recorded once `settled`, so a drag
+  // or a bit of typing ends up as a single step
+  pub fn track(&mut self, markers: Markers, settled: bool) {
+    if !settled || markers == self.current {
+      return;
+    }
+    self.undo.push(self.current);
+    if self.undo.len() > MAX_HISTORY {
+      self.undo.remove(0);
+    }
+    self.redo.clear();
+    self.current = markers;
+  }
+
+  pub fn undo(&mut self) -> Option<Markers> {
+    let markers = self.undo.pop()?;
+    self.redo.push(self.current);
+    self.current = markers;
+    Some(markers)
+  }
+
+  pub fn redo(&mut self) -> Option<Markers> {
+    let markers = self.redo.pop()?;
+    self.undo.push(self.current);
+    self.current = markers;
+    Some(markers)
+  }
+}
@@ -0,0 +1,570 @@
+use std::{
+  ffi::OsString,
+  fs::{self, File},
+  io::{self, BufRead, BufReader, Read, Write},
+  path::{Path, PathBuf},
+  process::{Child, Command, ExitStatus, Stdio},
+  sync::{Arc, Mutex},
+  thread::JoinHandle,
+  time::{Duration, Instant},
+};
+
+use crate::format::OutputFormat;
+
+// What a job makes, which decides whether its progress can be read off ffmpeg
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JobKind {
+  Video,
+  Audio,
+  // palettegen holds every frame back until it's seen the last one
+  Gif,
+  Frames,
+  Screenshot,
+}
+
+impl JobKind {
+  pub fn of(format: OutputFormat) -> Self {
+    if format == OutputFormat::Gif {
+      JobKind::Gif
+    } else if format.is_audio_only() {
+      JobKind::Audio
+    } else {
+      JobKind::Video
+    }
+  }
+
+  // Whether out_time moves along with the work, otherwise all there is to show is a spinner
+  fn timed(&self) -> bool {
+    !matches!(self, JobKind::Gif | JobKind::Screenshot)
+  }
+}
+
+pub struct Job {
+  in_progress: Arc<Mutex<bool>>,
+  progress: Arc<Mutex<f64>>,
+  speed: Arc<Mutex<Option<f64>>>,
+  child: Arc<Mutex<Option<Child>>>,
+  cancelled: Arc<Mutex<bool>>,
+  succeeded: Arc<Mutex<bool>>,
+  error: Arc<Mutex<Option<JobError>>>,
+  handle: Option<JoinHandle<()>>,
+  started: Option<Instant>,
+  kind: JobKind,
+  paused: Arc<Mutex<bool>>,
+  paused_since: Option<Instant>,
+  // Time spent paused, which doesn't count towards elapsed
+  paused_for: Duration,
+  log_path: Option<PathBuf>,
+}
+
+// How much of ffmpeg's stderr is kept around for error reports, the log file gets all of it
+const LOG_LINES: usize = 20;
+
+// Keeps the newest `keep` logs in `dir`, their names start with a timestamp so they sort by age
+pub fn prune_logs(dir: &Path, keep: usize) {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return;
+  };
+  let mut logs: Vec<PathBuf> = entries
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+    .collect();
+  logs.sort();
+  for path in &logs[..logs.len().saturating_sub(keep)] {
+    let _ = fs::remove_file(path);
+  }
+}
+
+#[derive(Clone)]
+pub struct JobError {
+  pub summary: String,
+  pub log: Vec<String>,
+}
+
+#[cfg(unix)]
+fn suspend(child: &Child, suspend: bool) -> io::Result<()> {
+  let signal = if suspend { libc::SIGSTOP } else { libc::SIGCONT };
+  match unsafe { libc::kill(child.id() as libc::pid_t, signal) } {
+    0 => Ok(()),
+    _ => Err(io::Error::last_os_error()),
+  }
+}
+
+#[cfg(windows)]
+fn suspend(child: &Child, suspend: bool) -> io::Result<()> {
+  use std::{ffi::c_void, os::windows::io::AsRawHandle};
+
+  // Undocumented but long-standing, and the only way to stop a whole process at once
+  #[link(name = "ntdll")]
+  extern "system" {
+    fn NtSuspendProcess(process: *mut c_void) -> i32;
+    fn NtResumeProcess(process: *mut c_void) -> i32;
+  }
+  let handle = child.as_raw_handle();
+  let status = unsafe { if suspend { NtSuspendProcess(handle) } else { NtResumeProcess(handle) } };
+  match status {
+    0.. => Ok(()),
+    _ => Err(io::Error::new(io::ErrorKind::Other, format!("NTSTATUS {:#x}", status))),
+  }
+}
+
+fn spawn(ffmpeg: &Path, args: &[OsString]) -> io::Result<Child> {
+  Command::new(ffmpeg)
+    .args(["-progress", "pipe:1", "-nostats"])
+    .args(args)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+}
+
+// Lines end at either \r or \n, whatever the platform
+fn read_line(reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<usize> {
+  let mut read = 0;
+  loop {
+    let available = reader.fill_buf()?;
+    if available.is_empty() {
+      return Ok(read);
+    }
+    match available.iter().position(|b| *b == b'\r' || *b == b'\n') {
+      Some(i) => {
+        buf.extend_from_slice(&available[..i]);
+        reader.consume(i + 1);
+        return Ok(read + i + 1);
+      },
+      None => {
+        let n = available.len();
+        buf.extend_from_slice(available);
+        reader.consume(n);
+        read += n;
+      },
+    }
+  }
+}
+
+enum Report {
+  // Milliseconds of output written so far
+  Position(f64),
+  // Multiple of realtime, like 3.2 for `speed=3.2x`
+  Speed(f64),
+}
+
+// -progress writes blocks of key=value lines, out_time_us is the position
+// in microseconds (out_time_ms is too, despite the name)
+fn parse_progress(line: &str, duration: f64) -> Option<Report> {
+  let (key, value) = line.trim().split_once('=')?;
+  match key {
+    "out_time_us" | "out_time_ms" => value.parse::<i64>().ok().map(|us| Report::Position(us as f64 / 1000.)),
+    "progress" if value == "end" => Some(Report::Position(duration)),
+    // N/A until ffmpeg has something to go on
+    "speed" => value.trim().trim_end_matches('x').parse().ok().map(Report::Speed),
+    _ => None,
+  }
+}
+
+fn read_progress(stdout: impl Read, duration: f64, mut report: impl FnMut(Report)) {
+  let mut reader = BufReader::new(stdout);
+  let mut line = vec![];
+  loop {
+    line.clear();
+    match read_line(&mut reader, &mut line) {
+      Ok(0) | Err(_) => break,
+      Ok(_) => {},
+    }
+    if let Some(parsed) = parse_progress(&String::from_utf8_lossy(&line), duration) {
+      report(parsed);
+    }
+  }
+}
+
+fn read_log(stderr: impl Read) -> Vec<String> {
+  let mut reader = BufReader::new(stderr);
+  let mut line = vec![];
+  let mut log = vec![];
+  loop {
+    line.clear();
+    match read_line(&mut reader, &mut line) {
+      Ok(0) | Err(_) => break,
+      Ok(_) => {},
+    }
+    let text = String::from_utf8_lossy(&line).trim_end().to_string();
+    if text.is_empty() {
+      continue;
+    }
+    log.push(text);
+  }
+  log
+}
+
+// Turns the usual suspects into something readable, otherwise ffmpeg's own last words
+fn describe(status: Option<ExitStatus>, log: &[String]) -> String {
+  let mentions = |needle: &str| log.iter().any(|line| line.contains(needle));
+  let hint = if mentions("No space left on device") {
+    "the disk is full".to_owned()
+  } else if mentions("Unknown encoder") || mentions("Encoder not found") {
+    "this ffmpeg build doesn't have the selected encoder".to_owned()
+  } else if mentions("Permission denied") {
+    "permission denied, check the output folder is writable".to_owned()
+  } else if mentions("Invalid data found when processing input") {
+    "the input couldn't be decoded".to_owned()
+  } else if mentions("does not contain any stream") {
+    "nothing would end up in the output, check the selected streams".to_owned()
+  } else {
+    log.last().cloned().unwrap_or_else(|| "no output from ffmpeg".to_owned())
+  };
+  match status {
+    Some(status) => format!("ffmpeg failed ({}): {}", status, hint),
+    None => format!("ffmpeg failed: {}", hint),
+  }
+}
+
+impl Job {
+  pub fn new() -> Self {
+    Self {
+      in_progress: Arc::new(Mutex::new(false)),
+      progress: Arc::new(Mutex::new(0.)),
+      speed: Arc::new(Mutex::new(None)),
+      child: Arc::new(Mutex::new(None)),
+      cancelled: Arc::new(Mutex::new(false)),
+      succeeded: Arc::new(Mutex::new(false)),
+      error: Arc::new(Mutex::new(None)),
+      handle: None,
+      started: None,
+      kind: JobKind::Video,
+      paused: Arc::new(Mutex::new(false)),
+      paused_since: None,
+      paused_for: Duration::ZERO,
+      log_path: None,
+    }
+  }
+
+  // The next job writes its commands and everything ffmpeg says to `path`
+  pub fn log_to(&mut self, path: PathBuf) {
+    self.log_path = Some(path);
+  }
+
+  pub fn in_progress(&self) -> bool {
+    *self.in_progress.lock().unwrap()
+  }
+
+  pub fn progress(&self) -> f64 {
+    *self.progress.lock().unwrap()
+  }
+
+  // The progress of jobs that can't be timed doesn't mean anything until they're done
+  pub fn is_indeterminate(&self) -> bool {
+    !self.kind.timed()
+  }
+
+  // None until ffmpeg has reported a speed and it's had a second to settle
+  pub fn speed(&self) -> Option<f64> {
+    if self.elapsed() < Duration::from_secs(1) {
+      return None;
+    }
+    *self.speed.lock().unwrap()
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_since.is_some()
+  }
+
+  // Stops ffmpeg in its tracks without losing anything, the clock stops too
+  pub fn set_paused(&mut self, paused: bool) -> io::Result<()> {
+    if paused == self.is_paused() {
+      return Ok(());
+    }
+    let child = self.child.lock().unwrap();
+    if let Some(child) = child.as_ref() {
+      suspend(child, paused)?;
+    }
+    *self.paused.lock().unwrap() = paused;
+    match self.paused_since.take() {
+      Some(since) => self.paused_for += since.elapsed(),
+      None => self.paused_since = Some(Instant::now()),
+    }
+    Ok(())
+  }
+
+  // Whether the last finished job ran every pass successfully
+  pub fn succeeded(&self) -> bool {
+    *self.succeeded.lock().unwrap()
+  }
+
+  // Why the last job failed, None if it succeeded or was cancelled
+  pub fn error(&self) -> Option<JobError> {
+    self.error.lock().unwrap().clone()
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    let paused = self.paused_for + self.paused_since.map(|since| since.elapsed()).unwrap_or_default();
+    self.started.map(|started| started.elapsed().saturating_sub(paused)).unwrap_or_default()
+  }
+
+  // None until there's enough progress to extrapolate from
+  pub fn eta(&self) -> Option<Duration> {
+    let elapsed = self.elapsed();
+    let progress = self.progress();
+    if self.is_indeterminate() || elapsed < Duration::from_secs(1) || progress < 0.001 {
+      return None;
+    }
+    Some(elapsed.mul_f64((1. - progress).max(0.) / progress))
+  }
+
+  // Runs each pass in order, `cleanup` is removed once the job is over
+  pub fn start(
+    &mut self,
+    ffmpeg: &Path,
+    passes: Vec<Vec<OsString>>,
+    kind: JobKind,
+    duration: f64,
+    cleanup: Vec<PathBuf>,
+    on_success: impl FnOnce() + Send + 'static,
+  ) -> io::Result<()> {
+    let Some(first) = passes.first() else {
+      return Ok(());
+    };
+    let child = spawn(ffmpeg, first)?;
+    *self.child.lock().unwrap() = Some(child);
+    *self.cancelled.lock().unwrap() = false;
+    *self.succeeded.lock().unwrap() = false;
+    *self.error.lock().unwrap() = None;
+    *self.progress.lock().unwrap() = 0.;
+    *self.speed.lock().unwrap() = None;
+    *self.in_progress.lock().unwrap() = true;
+    self.started = Some(Instant::now());
+    self.kind = kind;
+    *self.paused.lock().unwrap() = false;
+    self.paused_since = None;
+    self.paused_for = Duration::ZERO;
+
+    let ffmpeg = ffmpeg.to_path_buf();
+    let in_progress = self.in_progress.clone();
+    let progress = self.progress.clone();
+    let speed = self.speed.clone();
+    let child = self.child.clone();
+    let cancelled = self.cancelled.clone();
+    let succeeded = self.succeeded.clone();
+    let error = self.error.clone();
+    let paused = self.paused.clone();
+    let mut log_file = self.log_path.take().and_then(|path| File::create(path).ok());
+
+    self.handle = Some(std::thread::spawn(move || {
+      let count = passes.len() as f64;
+      let mut success = true;
+      let fail = |summary: String, log: Vec<String>| {
+        if !*cancelled.lock().unwrap() {
+          *error.lock().unwrap() = Some(JobError { summary, log });
+        }
+      };
+      for (pass, args) in passes.iter().enumerate() {
+        let (stdout, stderr) = {
+          let mut child = child.lock().unwrap();
+          if *cancelled.lock().unwrap() {
+            success = false;
+            break;
+          }
+          if pass > 0 {
+            match spawn(&ffmpeg, args) {
+              Ok(spawned) => {
+                // A pause that came in between passes carries over
+                if *paused.lock().unwrap() {
+                  let _ = suspend(&spawned, true);
+                }
+                *child = Some(spawned);
+              },
+              Err(err) => {
+                success = false;
+                drop(child);
+                fail(format!("failed to start ffmpeg: {}", err), vec![]);
+                break;
+              },
+            }
+          }
+          match child.as_mut() {
+            Some(c) => (c.stdout.take(), c.stderr.take()),
+            None => (None, None),
+          }
+        };
+
+        if let Some(file) = &mut log_file {
+          let command: Vec<String> = args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+          let _ = writeln!(file, "$ {} {}", ffmpeg.display(), command.join(" "));
+        }
+
+        // stderr has to be drained alongside stdout or ffmpeg can block on it
+        let log = stderr.map(|stderr| std::thread::spawn(move || read_log(stderr)));
+        if let Some(stdout) = stdout {
+          read_progress(stdout, duration, |report| match report {
+            // Positions come in unevenly and can step back a little, the bar only moves forwards
+            Report::Position(processed) => {
+              let mut progress = progress.lock().unwrap();
+              *progress = progress.max((pass as f64 + (processed / duration).min(1.)) / count);
+            },
+            Report::Speed(x) => *speed.lock().unwrap() = Some(x),
+          });
+        }
+        let mut log = log.and_then(|log| log.join().ok()).unwrap_or_default();
+        if let Some(file) = &mut log_file {
+          for line in &log {
+            let _ = writeln!(file, "{}", line);
+          }
+        }
+        log.drain(..log.len().saturating_sub(LOG_LINES));
+
+        // Cancelled jobs have already taken (and reaped) the child
+        let finished = child.lock().unwrap().take();
+        let status = finished.map(|mut c| c.wait());
+        if let (Some(file), Some(Ok(status))) = (&mut log_file, &status) {
+          let _ = writeln!(file, "exited with {}", status);
+        }
+        match status {
+          Some(Ok(status)) if status.success() => {},
+          Some(Ok(status)) => {
+            success = false;
+            fail(describe(Some(status), &log), log);
+            break;
+          },
+          _ => {
+            success = false;
+            fail(describe(None, &log), log);
+            break;
+          },
+        }
+      }
+      for path in cleanup {
+        let _ = fs::remove_file(path);
+      }
+      if success {
+        *progress.lock().unwrap() = 1.;
+        on_success();
+      }
+      *succeeded.lock().unwrap() = success;
+      *in_progress.lock().unwrap() = false;
+    }));
+    Ok(())
+  }
+
+  pub fn reset(&mut self) {
+    *self.progress.lock().unwrap() = 0.;
+  }
+
+  pub fn cancel(&mut self) {
+    let child = {
+      let mut child = self.child.lock().unwrap();
+      *self.cancelled.lock().unwrap() = true;
+      child.take()
+    };
+    if let Some(mut child) = child {
+      let _ = child.kill();
+      let _ = child.wait();
+    }
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+    *self.in_progress.lock().unwrap() = false;
+    *self.progress.lock().unwrap() = 0.;
+    *self.paused.lock().unwrap() = false;
+    self.paused_since = None;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Hands out the bytes in the chunks given, like a pipe would
+  struct Chunked(Vec<Vec<u8>>);
+
+  impl Read for Chunked {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      if self.0.is_empty() {
+        return Ok(0);
+      }
+      let chunk = &mut self.0[0];
+      let n = chunk.len().min(buf.len());
+      buf[..n].copy_from_slice(&chunk[..n]);
+      chunk.drain(..n);
+      if chunk.is_empty() {
+        self.0.remove(0);
+      }
+      Ok(n)
+    }
+  }
+
+  fn bytewise(text: &str) -> Chunked {
+    Chunked(text.bytes().map(|b| vec![b]).collect())
+  }
+
+  #[test]
+  fn reads_lines_a_byte_at_a_time() {
+    let log = read_log(bytewise("Input #0, from 'clíp ✂.mp4':\r\n  Duration: 00:00:05.00\rfrâme=  10\n"));
+    assert_eq!(log, ["Input #0, from 'clíp ✂.mp4':", "  Duration: 00:00:05.00", "frâme=  10"]);
+  }
+
+  #[test]
+  fn keeps_characters_split_across_chunks() {
+    let scissors = "✂".as_bytes();
+    let stderr = Chunked(vec![
+      b"cut ".to_vec(),
+      scissors[..1].to_vec(),
+      scissors[1..].to_vec(),
+      b" h\xc3".to_vec(),
+      b"\xa9\nlast line without a newline".to_vec(),
+    ]);
+    assert_eq!(read_log(stderr), ["cut ✂ hé", "last line without a newline"]);
+  }
+
+  #[test]
+  fn reads_progress_from_split_chunks() {
+    let stdout = Chunked(vec![b"out_time_us=15".to_vec(), b"00000\nspeed=2".to_vec(), b".5x\rprogress=end\n".to_vec()]);
+    let mut positions = vec![];
+    let mut speeds = vec![];
+    read_progress(stdout, 3000., |report| match report {
+      Report::Position(ms) => positions.push(ms),
+      Report::Speed(x) => speeds.push(x),
+    });
+    assert_eq!(positions, [1500., 3000.]);
+    assert_eq!(speeds, [2.5]);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn describes_exit_codes_and_signals() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let log = ["frame=  10".to_owned(), "out.mp4: No space left on device".to_owned()];
+    let code = describe(Some(ExitStatus::from_raw(1 << 8)), &log);
+    assert!(code.contains("exit status: 1") && code.ends_with("the disk is full"), "{}", code);
+    let killed = describe(Some(ExitStatus::from_raw(libc::SIGKILL)), &["Conversion failed!".to_owned()]);
+    assert!(killed.contains("signal: 9") && killed.ends_with("Conversion failed!"), "{}", killed);
+    assert_eq!(describe(None, &[]), "ffmpeg failed: no output from ffmpeg");
+  }
+
+  // A stand-in ffmpeg that complains and exits with 3
+  #[cfg(unix)]
+  #[test]
+  fn failing_command_reports_its_stderr() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("snip-job-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let ffmpeg = dir.join("ffmpeg");
+    fs::write(&ffmpeg, "#!/bin/sh\necho 'Input #0, from in.mp4' >&2\necho 'in.mp4: Invalid data found when processing input' >&2\nexit 3\n").unwrap();
+    fs::set_permissions(&ffmpeg, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut job = Job::new();
+    job.start(&ffmpeg, vec![vec!["-i".into(), "in.mp4".into()]], JobKind::Video, 1000., vec![], || {}).unwrap();
+    let started = Instant::now();
+    while job.in_progress() {
+      assert!(started.elapsed() < Duration::from_secs(10), "the job never finished");
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    assert!(!job.succeeded());
+    let error = job.error().expect("a failed job has an error");
+    assert!(error.summary.contains("exit status: 3"), "{}", error.summary);
+    assert!(error.summary.ends_with("the input couldn't be decoded"), "{}", error.summary);
+    assert_eq!(error.log, ["Input #0, from in.mp4", "in.mp4: Invalid data found when processing input"]);
+  }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use egui::{Align2, Area, Color32, Context, Event, Frame, Id, InputState, Key, Order, Rounding, Ui};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+  SetStart,
+  SetEnd,
+  QuickClip,
+  SelectAll,
+  ClearSelection,
+  CycleAudio,
+  PlayPause,
+  Rewind,
+  Pause,
+  Forward,
+  SeekBack,
+  SeekForward,
+  Snip,
+}
+
+impl Action {
+  pub const ALL: [Action; 13] = [
+    Action::SetStart,
+    Action::SetEnd,
+    Action::QuickClip,
+    Action::SelectAll,
+    Action::ClearSelection,
+    Action::CycleAudio,
+    Action::PlayPause,
+    Action::Rewind,
+    Action::Pause,
+    Action::Forward,
+    Action::SeekBack,
+    Action::SeekForward,
+    Action::Snip,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Action::SetStart => "Set start",
+      Action::SetEnd => "Set end",
+      Action::QuickClip => "Clip around the playhead",
+      Action::SelectAll => "Select all",
+      Action::ClearSelection => "Reset selection",
+      Action::CycleAudio => "Cycle audio channel",
+      Action::PlayPause => "Play/pause",
+      Action::Rewind => "Play backwards (faster on repeat)",
+      Action::Pause => "Pause",
+      Action::Forward => "Play forwards (faster on repeat)",
+      Action::SeekBack => "Seek back",
+      Action::SeekForward => "Seek forward",
+      Action::Snip => "Snip",
+    }
+  }
+
+  fn default_key(&self) -> Key {
+    match self {
+      Action::SetStart => Key::S,
+      Action::SetEnd => Key::E,
+      Action::QuickClip => Key::Q,
+      Action::SelectAll => Key::F,
+      Action::ClearSelection => Key::Delete,
+      Action::CycleAudio => Key::A,
+      Action::PlayPause => Key::Space,
+      Action::Rewind => Key::J,
+      Action::Pause => Key::K,
+      Action::Forward => Key::L,
+      Action::SeekBack => Key::ArrowLeft,
+      Action::SeekForward => Key::ArrowRight,
+      Action::Snip => Key::Enter,
+    }
+  }
+}
+
+// Shortcuts that can't be remapped, for the cheat sheet
+const FIXED: [(&str, &str); 10] = [
+  ("M", "Mute preview"),
+  ("Shift+M", "All audio streams/only the previewed one"),
+  ("Shift+L", "Loop selection"),
+  ("P", "Save the current frame"),
+  (", / .", "Previous/next frame"),
+  ("[ / ]", "Jump to start/end"),
+  ("Shift+arrows", "Fine seek"),
+  ("Scroll over the preview", "Seek"),
+  ("Ctrl+Z / Ctrl+Y", "Undo/redo start and end"),
+  ("? / F1", "Show/hide this list"),
+];
+
+// Keys the fixed shortcuts take whatever the modifiers, so an action bound to
+// one would fire along with them. The punctuation ones and ? can't be bound at all
+const FIXED_KEYS: [(Key, &str); 2] = [
+  (Key::M, "Mute preview"),
+  (Key::P, "Save the current frame"),
+];
+
+// Escape cancels picking a key and closes the cheat sheet, F1 opens it
+const RESERVED: [Key; 2] = [Key::Escape, Key::F1];
+
+#[derive(Default)]
+pub struct Keybinds {
+  // Only actions that were remapped are stored, the rest use their default key
+  keys: HashMap<Action, Key>,
+  listening: Option<Action>,
+  refused: Option<Key>,
+}
+
+impl Keybinds {
+  pub fn new(keys: HashMap<Action, Key>) -> Self {
+    Self { keys, listening: None, refused: None }
+  }
+
+  pub fn keys(&self) -> HashMap<Action, Key> {
+    self.keys.clone()
+  }
+
+  pub fn key(&self, action: Action) -> Key {
+    self.keys.get(&action).copied().unwrap_or_else(|| action.default_key())
+  }
+
+  // Shortcuts are held off while a key is being picked so it doesn't fire too.
+  // Ctrl+Z/Y (undo/redo) and Shift+L (loop selection) are left to the fixed shortcuts
+  pub fn pressed(&self, input: &InputState, action: Action) -> bool {
+    let key = self.key(action);
+    self.listening.is_none()
+      && !input.modifiers.command
+      && !(key == Key::L && input.modifiers.shift)
+      && input.key_pressed(key)
+  }
+
+  // Every shortcut as it's currently bound, drawn over everything else
+  pub fn cheat_sheet(&self, ctx: &Context) {
+    Area::new(Id::new("cheat_sheet"))
+      .anchor(Align2::CENTER_CENTER, [0., 0.])
+      .order(Order::Foreground)
+      .interactable(false)
+      .show(ctx, |ui| {
+        Frame::none()
+          .fill(Color32::from_black_alpha(220))
+          .rounding(Rounding::same(6.))
+          .inner_margin(12.)
+          .show(ui, |ui| {
+            ui.heading("Keyboard shortcuts");
+            egui::Grid::new("cheat_sheet_grid").show(ui, |ui| {
+              let keys = Action::ALL.into_iter().map(|action| (self.key(action).name(), action.name()));
+              for (key, name) in keys.chain(FIXED) {
+                ui.colored_label(Color32::WHITE, key);
+                ui.colored_label(Color32::LIGHT_GRAY, name);
+                ui.end_row();
+              }
+            });
+          });
+      });
+  }
+
+  fn conflicts(&self, action: Action) -> Vec<&'static str> {
+    let key = self.key(action);
+    Action::ALL.into_iter()
+      .filter(|other| *other != action && self.key(*other) == key)
+      .map(|other| other.name())
+      .chain(FIXED_KEYS.into_iter().filter(|(fixed, _)| *fixed == key).map(|(_, name)| name))
+      .collect()
+  }
+
+  pub fn ui(&mut self, ui: &mut Ui) {
+    if let Some(action) = self.listening {
+      let pressed = ui.input(|i| i.events.iter().find_map(|event| match event {
+        Event::Key { key, pressed: true, .. } => Some(*key),
+        _ => None,
+      }));
+      match pressed {
+        Some(Key::Escape) => {
+          self.listening = None;
+          self.refused = None;
+        },
+        Some(key) if RESERVED.contains(&key) => self.refused = Some(key),
+        Some(key) => {
+          self.keys.insert(action, key);
+          self.listening = None;
+          self.refused = None;
+        },
+        None => {},
+      }
+    }
+
+    egui::Grid::new("keybinds").striped(true).show(ui, |ui| {
+      for action in Action::ALL {
+        ui.label(action.name());
+        let text = if self.listening == Some(action) { "press a key..." } else { self.key(action).name() };
+        if ui.button(text).clicked() {
+          self.listening = Some(action);
+          self.refused = None;
+        }
+        let conflicts = self.conflicts(action);
+        if !conflicts.is_empty() {
+          ui.colored_label(Color32::RED, format!("also bound to {}", conflicts.join(", ")));
+        }
+        ui.end_row();
+      }
+    });
+    if self.listening.is_some() {
+      ui.label("escape cancels");
+    }
+    if let Some(key) = self.refused.filter(|_| self.listening.is_some()) {
+      ui.colored_label(Color32::RED, format!("{} is taken by the cheat sheet, pick another key", key.name()));
+    }
+    if ui.button("Reset to defaults").clicked() {
+      self.keys.clear();
+      self.listening = None;
+      self.refused = None;
+    }
+  }
+}
@@ -0,0 +1,72 @@
+use std::{
+  path::Path,
+  sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+};
+
+use crate::probe;
+
+#[derive(Default)]
+pub struct Keyframes {
+  times: Arc<Mutex<Option<Vec<i64>>>>,
+  // Set if ffprobe couldn't list them, `times` then stays None
+  failed: Arc<AtomicBool>,
+}
+
+impl Keyframes {
+  pub fn load(ffmpeg: &Path, file: &Path) -> Self {
+    let times = Arc::new(Mutex::new(None));
+    let failed = Arc::new(AtomicBool::new(false));
+    {
+      let times = times.clone();
+      let failed = failed.clone();
+      let ffmpeg = ffmpeg.to_path_buf();
+      let file = file.to_path_buf();
+      std::thread::spawn(move || {
+        match probe::probe_keyframes(&ffmpeg, &file) {
+          Some(probed) => *times.lock().unwrap() = Some(probed),
+          None => failed.store(true, Ordering::Relaxed),
+        }
+      });
+    }
+    Self { times, failed }
+  }
+
+  pub fn is_loaded(&self) -> bool {
+    self.times.lock().unwrap().is_some()
+  }
+
+  pub fn failed(&self) -> bool {
+    self.failed.load(Ordering::Relaxed)
+  }
+
+  pub fn between(&self, from: i64, to: i64) -> Vec<i64> {
+    let times = self.times.lock().unwrap();
+    let Some(times) = times.as_ref() else {
+      return vec![];
+    };
+    let first = times.partition_point(|t| *t < from);
+    let last = times.partition_point(|t| *t <= to);
+    times[first..last.max(first)].to_vec()
+  }
+
+  pub fn nearest(&self, ms: i64) -> Option<i64> {
+    let times = self.times.lock().unwrap();
+    let times = times.as_ref()?;
+    let i = times.partition_point(|t| *t < ms);
+    [i.checked_sub(1), Some(i)].into_iter()
+      .flatten()
+      .filter_map(|i| times.get(i).copied())
+      .min_by_key(|t| (t - ms).abs())
+  }
+
+  // The last keyframe at or before `ms`
+  pub fn preceding(&self, ms: i64) -> Option<i64> {
+    let times = self.times.lock().unwrap();
+    let times = times.as_ref()?;
+    match times.binary_search(&ms) {
+      Ok(i) => Some(times[i]),
+      Err(0) => None,
+      Err(i) => Some(times[i - 1]),
+    }
+  }
+}
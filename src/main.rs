@@ -1,11 +1,76 @@
 // #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{path::PathBuf, process::Stdio, io::Read, sync::{Mutex, Arc}, thread::JoinHandle, fs, env::args};
+use std::{collections::HashMap, ffi::OsString, path::{Path, PathBuf}, process::{Command, Stdio}, fs::{self, FileTimes}, io, env::args_os, time::{Duration, SystemTime, UNIX_EPOCH}};
 
-use egui::{CentralPanel, Color32, Key, Slider};
+use egui::{Button, CentralPanel, Color32, ComboBox, DragValue, Event, InputState, Key, ProgressBar, Slider, TextEdit, Ui, Visuals};
 use egui_video::{AudioDevice, Player, PlayerState};
-use regex::Regex;
+use notify_rust::Notification;
 use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+
+mod batch;
+mod caption;
+mod cli;
+mod concat;
+mod crop;
+mod format;
+mod gif_preview;
+mod history;
+mod job;
+mod keybinds;
+mod keyframes;
+mod marker;
+mod preset;
+mod probe;
+mod queue;
+mod settings;
+mod silence;
+mod speed;
+mod taskbar;
+mod thumbnail;
+mod timeline;
+mod toast;
+mod waveform;
+
+use format::{
+  crf_hint, is_hardware_encoder, AudioCodec, OutputFormat, Resolution, Rotation, H264_ENCODERS, VIDEO_EXTENSIONS, X264_PRESETS,
+};
+use batch::Batch;
+use caption::{Caption, Corner};
+use cli::{Cli, USAGE};
+use concat::{concat_args, Clip};
+use crop::Crop;
+use gif_preview::GifPreview;
+use history::History;
+use job::{Job, JobKind};
+use keybinds::{Action, Keybinds};
+use keyframes::Keyframes;
+use marker::Marker;
+use preset::Preset;
+use probe::{AudioStream, VideoInfo, VideoStream};
+use queue::{Fallback, JobStatus, SnipJob};
+use settings::{Settings, SETTINGS_KEY};
+use silence::{Detection, Silence};
+use speed::{PlaybackSpeed, SPEEDS};
+use taskbar::Taskbar;
+use thumbnail::{Boundaries, Thumbnails};
+use timeline::Timeline;
+use toast::Toasts;
+use waveform::Waveform;
+
+const MAX_RECENT_FILES: usize = 10;
+const SCROLL_NOTCH: f32 = 50.;
+const MINI_CONTROLS_HEIGHT: f32 = 140.;
+// Logs of older jobs past this many are deleted
+const MAX_LOGS: usize = 20;
+// How far off a stream copy's length can be before it's pointed out
+const TRIM_TOLERANCE_MS: f64 = 100.;
+// EBU R128 targets, roughly what streaming sites aim for
+const LOUDNORM: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
+#[cfg(windows)]
+const NULL_OUTPUT: &str = "NUL";
+#[cfg(not(windows))]
+const NULL_OUTPUT: &str = "/dev/null";
 
 fn format_ms(ms: i64) -> String {
   let h = ms / 3600000;
@@ -15,163 +80,1967 @@ fn format_ms(ms: i64) -> String {
   format!("{:02}:{:02}:{:02}.{:03}", h, m, s, ms)
 }
 
+// `name (1).ext`, `name (2).ext`... whichever is free first
+fn unused_path(path: &Path) -> PathBuf {
+  let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+  let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+  (1..)
+    .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+    .find(|path| !path.exists())
+    .unwrap()
+}
+
+fn format_duration(d: Duration) -> String {
+  let s = d.as_secs();
+  if s >= 3600 {
+    format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+  } else {
+    format!("{:02}:{:02}", s / 60, s % 60)
+  }
+}
+
+fn parse_ms(s: &str) -> Option<i64> {
+  let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+  let parts: Vec<&str> = s.trim().split(':').collect();
+  let [h, m, s] = parts[..] else {
+    return None;
+  };
+  let (s, ms) = s.split_once('.').unwrap_or((s, "0"));
+  if !(digits(h) && digits(m) && digits(s) && digits(ms)) || ms.len() > 3 {
+    return None;
+  }
+  let h = h.parse::<i64>().ok()?;
+  let m = m.parse::<i64>().ok()?;
+  let s = s.parse::<i64>().ok()?;
+  let ms = format!("{:0<3}", ms).parse::<i64>().ok()?;
+  if m >= 60 || s >= 60 {
+    return None;
+  }
+  Some(h * 3600000 + m * 60000 + s * 1000 + ms)
+}
+
+// ffmpeg reads a relative `name:rest` as a protocol, but never an absolute path
+pub fn absolute(path: PathBuf) -> PathBuf {
+  match std::env::current_dir() {
+    Ok(dir) if path.is_relative() => dir.join(path),
+    _ => path,
+  }
+}
+
+// Looser than parse_ms, for timestamps copied from elsewhere: `90.5`, `1:30`, `1:02:03.250`
+fn parse_timecode(s: &str) -> Option<i64> {
+  let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+  let (whole, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+  let parts: Vec<&str> = whole.split(':').collect();
+  if parts.len() > 3 || !digits(fraction) || parts.iter().any(|part| part.is_empty() || !digits(part)) {
+    return None;
+  }
+  let mut seconds = 0;
+  for (i, part) in parts.iter().enumerate() {
+    let n = part.parse::<i64>().ok()?;
+    if i > 0 && n >= 60 {
+      return None;
+    }
+    seconds = seconds * 60 + n;
+  }
+  let ms = match fraction {
+    "" => 0,
+    fraction => (format!("0.{}", fraction).parse::<f64>().ok()? * 1000.).round() as i64,
+  };
+  Some(seconds * 1000 + ms)
+}
+
+fn clipboard_text() -> Option<String> {
+  arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+// Sets `marker` to the timecode on the clipboard, toasting when there isn't one
+fn paste_marker(marker: &mut Option<i64>, toasts: &mut Toasts) {
+  let text = clipboard_text().unwrap_or_default();
+  match parse_timecode(&text) {
+    Some(ms) => *marker = Some(ms),
+    None if text.trim().is_empty() => toasts.error("Nothing to paste"),
+    None => toasts.error(format!("`{}` isn't a timecode", text.trim())),
+  }
+}
+
+fn time_field(ui: &mut Ui, text: &mut String, value: &mut Option<i64>) {
+  let valid = text.trim().is_empty() || parse_ms(text).is_some();
+  let response = ui.add(TextEdit::singleline(text)
+    .hint_text("not set")
+    .desired_width(100.)
+    .text_color_opt((!valid).then_some(Color32::RED)));
+  if response.changed() {
+    if text.trim().is_empty() {
+      *value = None;
+    } else if let Some(ms) = parse_ms(text) {
+      *value = Some(ms);
+    }
+  }
+  if !response.has_focus() {
+    *text = value.map(format_ms).unwrap_or_default();
+  }
+}
+
+// Drags in milliseconds, an unset marker shows `unset` until it's first dragged
+fn ms_field(ui: &mut Ui, value: &mut Option<i64>, unset: i64, range: std::ops::RangeInclusive<i64>) {
+  let mut ms = value.unwrap_or(unset);
+  if ui.add(DragValue::new(&mut ms).clamp_range(range).speed(10).suffix(" ms")).changed() {
+    *value = Some(ms);
+  }
+}
+
+fn target_bitrate_kbps(target_mb: f64, duration_ms: f64, audio_kbps: u64) -> u64 {
+  let total_kbps = target_mb * 8. * 1024. * 1024. / 1000. / (duration_ms / 1000.).max(0.001);
+  (total_kbps as u64).saturating_sub(audio_kbps).max(1)
+}
+
+// Rough x264 bits per pixel per frame at CRF 23, doubling every 6 CRF steps down
+fn crf_bits_per_pixel(crf: u8) -> f64 {
+  0.1 * 2f64.powf((23. - crf as f64) / 6.)
+}
+
+fn is_video(path: &Path) -> bool {
+  path.extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+// Windows won't delete a file another program has open, which is often just a viewer letting go
+fn remove_with_retry(path: &Path) -> io::Result<()> {
+  let mut delay = Duration::from_millis(50);
+  for _ in 0..4 {
+    match fs::remove_file(path) {
+      Err(err) if err.kind() != io::ErrorKind::NotFound => std::thread::sleep(delay),
+      result => return result,
+    }
+    delay *= 2;
+  }
+  fs::remove_file(path)
+}
+
+// The numbered pngs `export_frames` writes
+fn frame_files(dir: &Path) -> Vec<PathBuf> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return vec![];
+  };
+  entries.filter_map(|entry| Some(entry.ok()?.path()))
+    .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("frame_") && name.ends_with(".png")))
+    .collect()
+}
+
+fn copy_modified_time(from: &Path, to: &Path) -> io::Result<()> {
+  let modified = fs::metadata(from)?.modified()?;
+  fs::File::options()
+    .write(true)
+    .open(to)?
+    .set_times(FileTimes::new().set_modified(modified))
+}
+
+fn shell_quote(arg: &str) -> String {
+  let safe = |c: char| c.is_ascii_alphanumeric() || "-_.,:/=+@%".contains(c);
+  if !arg.is_empty() && arg.chars().all(safe) {
+    arg.to_owned()
+  } else if cfg!(windows) {
+    format!("\"{}\"", arg.replace('"', "\\\""))
+  } else {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+  }
+}
+
+fn open_detached(command: &mut Command) -> io::Result<()> {
+  let mut child = command.spawn()?;
+  std::thread::spawn(move || child.wait());
+  Ok(())
+}
+
+// Highlights the file where the file manager supports it, otherwise just opens its folder
+fn reveal(path: &Path) -> io::Result<()> {
+  let dir = path.parent().unwrap_or(Path::new("."));
+  if cfg!(windows) {
+    let mut select = std::ffi::OsString::from("/select,");
+    select.push(path);
+    open_detached(Command::new("explorer").arg(select))
+      .or_else(|_| open_detached(Command::new("explorer").arg(dir)))
+  } else if cfg!(target_os = "macos") {
+    open_detached(Command::new("open").arg("-R").arg(path))
+      .or_else(|_| open_detached(Command::new("open").arg(dir)))
+  } else {
+    open_detached(Command::new("xdg-open").arg(dir))
+  }
+}
+
+// Single-key shortcuts stay quiet while a text field has focus
+fn shortcut(ui: &Ui, pressed: impl FnOnce(&InputState) -> bool) -> bool {
+  !ui.ctx().wants_keyboard_input() && ui.input(pressed)
+}
+
+fn text_typed(ui: &Ui, text: &str) -> bool {
+  shortcut(ui, |i| i.events.iter().any(|e| matches!(e, Event::Text(t) if t == text)))
+}
+
+fn check_ffmpeg(path: &Path) -> Option<String> {
+  match Command::new(path)
+    .arg("-version")
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+  {
+    Ok(status) if status.success() => None,
+    Ok(status) => Some(format!("`{} -version` exited with {}", path.display(), status)),
+    Err(err) => Some(format!("failed to run ffmpeg at `{}`: {}", path.display(), err)),
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewSize {
+  Fit,
+  Actual,
+  Zoom,
+}
+
+impl PreviewSize {
+  const ALL: [PreviewSize; 3] = [PreviewSize::Fit, PreviewSize::Actual, PreviewSize::Zoom];
+
+  fn name(&self) -> &'static str {
+    match self {
+      PreviewSize::Fit => "Fit",
+      PreviewSize::Actual => "Actual size",
+      PreviewSize::Zoom => "Zoom",
+    }
+  }
+}
+
+enum Overwrite {
+  Replace,
+  Rename,
+  Cancel,
+}
+
 struct SnipApp {
-  audio_device: AudioDevice,
-  file_path: PathBuf,
+  // Machines without an audio output still get a silent preview
+  audio_device: Result<AudioDevice, String>,
+  ffmpeg_path: PathBuf,
+  temp_dir: Option<PathBuf>,
+  ffmpeg_error: Option<String>,
+  file_path: Option<PathBuf>,
+  recent_files: Vec<PathBuf>,
+  // The rest of the files passed on the command line, to go through one by one
+  up_next: Vec<PathBuf>,
+  positions: HashMap<PathBuf, i64>,
+  // The open file's markers, the other files' are in `saved_markers`
+  markers: Vec<Marker>,
+  saved_markers: HashMap<PathBuf, Vec<Marker>>,
+  marker_label: String,
+  fps: f64,
+  video_streams: Vec<VideoStream>,
+  video_stream: usize,
+  audio_streams: Vec<AudioStream>,
+  audio_selected: Vec<bool>,
+  audio_layouts: HashMap<String, Vec<bool>>,
+  info: Option<VideoInfo>,
+  audio_stream: usize,
   player: Option<Player>,
+  player_error: Option<String>,
+  player_loading: bool,
+  waveform: Waveform,
+  // Every file's waveform cache from this session, removed on exit
+  waveform_caches: Vec<PathBuf>,
+  keyframes: Keyframes,
+  snap_to_keyframe: bool,
+  show_keyframes: bool,
+  timeline: Timeline,
+  thumbnails: Thumbnails,
+  boundaries: Boundaries,
+  gif_preview: GifPreview,
+  crop: Crop,
+  volume: f32,
+  muted: bool,
+  speed: PlaybackSpeed,
+  scroll: f32,
+  seek_step_ms: i64,
+  seek_step_fine_ms: i64,
+  quick_clip_ms: i64,
+  silence: Silence,
+  // What counts as silence when trimming it off the ends
+  silence_db: f64,
+  silence_ms: i64,
+  frames_fps: f64,
+  loop_selection: bool,
   start: Option<i64>,
   end: Option<i64>,
-  merge_or_map: bool,
-  audio_merge: u8,
+  // Exported back to back instead of start/end when not empty
+  segments: Vec<(i64, i64)>,
+  start_text: String,
+  end_text: String,
+  // A typed end past the end of the file, kept to explain why it was clamped
+  end_clamped: Option<i64>,
+  history: History,
+  copy_streams: bool,
+  copy_fallback: bool,
+  format: OutputFormat,
+  extra_formats: Vec<OutputFormat>,
+  resolution: Resolution,
+  // 0 keeps the source framerate
+  output_fps: f64,
+  letterbox: bool,
+  pad_color: [u8; 3],
+  rotation: Rotation,
+  caption: Caption,
+  encoder: String,
+  encoders: Vec<&'static str>,
+  crf: u8,
+  preset: String,
+  presets: Vec<Preset>,
+  preset_name: String,
+  preset_error: Option<String>,
+
+  target_size: bool,
+  target_size_mb: f64,
+  copy_metadata: bool,
+  normalize_audio: bool,
+  audio_codec: AudioCodec,
+  audio_kbps: u64,
+  fast_seek: bool,
+  fade_in_ms: u32,
+  fade_out_ms: u32,
+
+  reveal_output: bool,
+  last_output_dir: Option<PathBuf>,
+  output_dir: Option<PathBuf>,
+  notify: bool,
 
-  in_progress: Arc<Mutex<bool>>,
-  progress: Arc<Mutex<f64>>,
-  ffmpeg_handle: Option<JoinHandle<()>>,
+  keybinds: Keybinds,
+  keybinds_open: bool,
+  cheat_sheet_open: bool,
+  light_theme: bool,
+  // Only the preview, timeline and the controls needed to snip
+  mini: bool,
+  preview_size: PreviewSize,
+  preview_zoom: f32,
+  toasts: Toasts,
+  // A job whose output already exists, and whether it was headed for the queue
+  overwrite: Option<(SnipJob, bool)>,
+  confirm_export: bool,
+  // A snip waiting to be confirmed, and the "don't ask again" box
+  confirm: Option<(SnipJob, bool)>,
+  // A copied snip and its actual length in ms, when that's not what was asked for
+  trim_mismatch: Option<(SnipJob, f64)>,
+  // The last job's log, and its contents while it's being viewed
+  log: Option<PathBuf>,
+  log_view: Option<String>,
+
+  job: Job,
+  // A close request came in while ffmpeg was running
+  quit_prompt: bool,
+  quit_when_done: bool,
+  allowed_to_close: bool,
+  running: Option<SnipJob>,
+  was_in_progress: bool,
+  title_progress: taskbar::Progress,
+  taskbar: Taskbar,
+  output: Option<PathBuf>,
+  // Frames already in the folder of a running frame export, so cancelling only removes new ones
+  frames_before: Option<Vec<PathBuf>>,
+  queue: Vec<SnipJob>,
+  processing_queue: bool,
+  clips: Vec<Clip>,
+  batch: Batch,
 }
 
 impl SnipApp {
-  pub fn snip(&mut self) {
-    if let Some(new) = FileDialog::new()
-      .add_filter("Video", &["mp4"])
-      .set_file_name("video.mp4")
-      .save_file()
-    {
-      if new.exists() {
-        fs::remove_file(&new).unwrap();
+  // A fresh log for a job making `output`, making room for it among the old ones
+  fn log_path(&self, output: &Path) -> Option<PathBuf> {
+    let dir = self.scratch_dir().join("snip-logs");
+    fs::create_dir_all(&dir).ok()?;
+    job::prune_logs(&dir, MAX_LOGS - 1);
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    Some(dir.join(format!("{}-{}.log", secs, stem)))
+  }
+
+  fn start_log(&mut self, output: &Path) {
+    self.log = self.log_path(output);
+    if let Some(log) = &self.log {
+      self.job.log_to(log.clone());
+    }
+  }
+
+  // Falls back to the OS temp directory if the chosen one has gone away
+  fn scratch_dir(&self) -> PathBuf {
+    self.temp_dir.clone().filter(|dir| dir.is_dir()).unwrap_or_else(std::env::temp_dir)
+  }
+
+  // Fast seeking jumps to the nearest keyframe before decoding, which is much
+  // quicker on long files but can land slightly off on some formats. Without
+  // it ffmpeg decodes everything up to the start
+  fn input_args(&self, file: &Path) -> Vec<OsString> {
+    let mut args = vec![];
+    if let Some(start) = self.start.filter(|_| self.fast_seek && self.segments.is_empty()) {
+      args.push("-ss".into());
+      args.push(format_ms(start).into());
+    }
+    args.push("-i".into());
+    args.push(file.as_os_str().into());
+    args
+  }
+
+  // These are output options, so they go after every input
+  fn seek_args(&self) -> Vec<OsString> {
+    let mut args = vec![];
+    // Segments are trimmed in the filtergraph instead
+    if !self.segments.is_empty() {
+      return args;
+    }
+    if self.fast_seek {
+      // Timestamps restart at 0 after an input seek, so the end becomes a length
+      if let Some(end) = self.end {
+        args.push("-t".into());
+        args.push(format_ms(end - self.start.unwrap_or(0)).into());
       }
+      return args;
+    }
+    if let Some(start) = self.start {
+      args.push("-ss".into());
+      args.push(format_ms(start).into());
+    }
+    if let Some(end) = self.end {
+      args.push("-to".into());
+      args.push(format_ms(end).into());
+    }
+    args
+  }
+
+  // Where the clip starts on the timestamps the filters see
+  fn filter_offset(&self) -> f64 {
+    if self.fast_seek || !self.segments.is_empty() { 0. } else { self.start.unwrap_or(0) as f64 }
+  }
 
-      let mut args = vec![];
-      if let Some(start) = self.start {
-        args.push("-ss".to_owned());
-        args.push(format_ms(start));
+  // Length of what gets exported, in ms, 0 if start is after end
+  fn clip_duration(&self, duration_ms: i64) -> f64 {
+    if self.segments.is_empty() {
+      (self.end.unwrap_or(duration_ms) - self.start.unwrap_or(0)).max(0) as f64
+    } else {
+      self.segments.iter().map(|(start, end)| end - start).sum::<i64>() as f64
+    }
+  }
+
+  // Cuts each segment out of the input and joins them into [sv] and [sa]
+  fn segment_filter(&self, video: bool, audio: &[usize]) -> Option<String> {
+    if self.segments.is_empty() {
+      return None;
+    }
+    let mut filters = vec![];
+    let mut inputs = String::new();
+    for (i, (start, end)) in self.segments.iter().enumerate() {
+      let trim = format!("start={}:end={}", *start as f64 / 1000., *end as f64 / 1000.);
+      if video {
+        filters.push(format!("[0:v:{}]trim={},setpts=PTS-STARTPTS[v{}]", self.video_stream, trim, i));
+        inputs.push_str(&format!("[v{}]", i));
       }
-      if let Some(end) = self.end {
-        args.push("-to".to_owned());
-        args.push(format_ms(end));
-      }
-      args.push("-i".to_owned());
-      args.push(self.file_path.to_str().unwrap().to_string());
-      args.push("-c:v".to_owned());
-      args.push("libx264".to_owned());
-      if self.merge_or_map {
-        args.push("-filter_complex".to_owned());
-        args.push(format!("amerge=inputs={}", self.audio_merge));
+      if !audio.is_empty() {
+        let streams: String = audio.iter().map(|s| format!("[0:a:{}]", s)).collect();
+        let merge = if audio.len() > 1 { format!("amerge=inputs={},", audio.len()) } else { String::new() };
+        filters.push(format!("{}{}atrim={},asetpts=PTS-STARTPTS[a{}]", streams, merge, trim, i));
+        inputs.push_str(&format!("[a{}]", i));
+      }
+    }
+    let outputs = format!("{}{}", if video { "[sv]" } else { "" }, if audio.is_empty() { "" } else { "[sa]" });
+    filters.push(format!(
+      "{}concat=n={}:v={}:a={}{}",
+      inputs, self.segments.len(), video as u8, !audio.is_empty() as u8, outputs,
+    ));
+    Some(filters.join(";"))
+  }
+
+  // Pushes the video filters onto `filters`, returning what to -map
+  fn video_map(&self, filters: &mut Vec<String>, duration: f64) -> String {
+    let input = if self.segments.is_empty() { format!("[0:v:{}]", self.video_stream) } else { "[sv]".to_owned() };
+    match self.video_filter(duration) {
+      Some(filter) => {
+        filters.push(format!("{}{}[v]", input, filter));
+        "[v]".to_owned()
+      },
+      None if self.segments.is_empty() => format!("0:v:{}", self.video_stream),
+      None => input,
+    }
+  }
+
+  // `filter` is either fade or afade, `duration` is the trimmed clip length in ms
+  fn fades(&self, filter: &str, duration: f64) -> Vec<String> {
+    let offset = self.filter_offset();
+    let mut fades = vec![];
+    if self.fade_in_ms > 0 {
+      fades.push(format!("{}=t=in:st={}:d={}", filter, offset / 1000., self.fade_in_ms as f64 / 1000.));
+    }
+    if self.fade_out_ms > 0 {
+      let length = (self.fade_out_ms as f64).min(duration);
+      fades.push(format!("{}=t=out:st={}:d={}", filter, (offset + duration - length) / 1000., length / 1000.));
+    }
+    fades
+  }
+
+  // Crop happens first so its bounds stay in source pixels, then rotation,
+  // so scaling targets the final orientation
+  fn video_filter(&self, duration: f64) -> Option<String> {
+    let rotate = self.rotation.filter().map(String::from);
+    let scale = match (self.letterbox, self.resolution.size()) {
+      (true, Some((w, h))) => {
+        let [r, g, b] = self.pad_color;
+        Some(format!(
+          "scale={0}:{1}:force_original_aspect_ratio=decrease,pad={0}:{1}:(ow-iw)/2:(oh-ih)/2:color=0x{2:02x}{3:02x}{4:02x}",
+          w, h, r, g, b,
+        ))
+      },
+      _ => self.resolution.height().map(|height| format!("scale=-2:{}", height)),
+    };
+    // Dropping frames first leaves less for the other filters to do
+    let fps = (self.output_fps > 0.).then(|| format!("fps={}", self.output_fps));
+    let filters: Vec<String> = fps.into_iter()
+      .chain(self.crop.filter())
+      .chain(rotate)
+      .chain(scale)
+      .chain(self.caption.filter().filter(|_| self.subtitle_codec().is_none()))
+      .chain(self.fades("fade", duration))
+      .collect();
+    (!filters.is_empty()).then(|| filters.join(","))
+  }
+
+  // Falls back to the format's codec when the chosen one doesn't fit it, and
+  // segments can't be copied since they're trimmed by filters
+  fn audio_encoder(&self) -> Option<&'static str> {
+    self.audio_codec.encoder()
+      .filter(|codec| self.audio_codec.fits(self.format) && (*codec != "copy" || self.segments.is_empty()))
+      .or(self.format.audio_codec())
+  }
+
+  fn audio_codec_args(&self) -> Vec<OsString> {
+    let Some(codec) = self.audio_encoder() else {
+      return vec![];
+    };
+    let mut args: Vec<OsString> = vec!["-c:a".into(), codec.into()];
+    if !matches!(codec, "copy" | "pcm_s16le") {
+      args.push("-b:a".into());
+      args.push(format!("{}k", self.audio_kbps).into());
+    }
+    args
+  }
+
+  fn audio_filter(&self, duration: f64) -> Option<String> {
+    // Copied audio can't be filtered
+    if self.audio_encoder() == Some("copy") {
+      return None;
+    }
+    // loudnorm upsamples to 192kHz, so it's brought back down afterwards
+    let normalize = [LOUDNORM, "aresample=48000"].map(String::from).into_iter().filter(|_| self.normalize_audio);
+    let filters: Vec<String> = normalize.chain(self.fades("afade", duration)).collect();
+    (!filters.is_empty()).then(|| filters.join(","))
+  }
+
+  // Set when the caption goes in as a subtitle track rather than being burned in
+  fn subtitle_codec(&self) -> Option<&'static str> {
+    self.format.subtitle_codec()
+      .filter(|_| self.caption.soft && !self.caption.is_empty() && !self.copy_streams)
+  }
+
+  fn selected_audio(&self) -> Vec<usize> {
+    (0..self.audio_streams.len()).filter(|i| self.audio_selected[*i]).collect()
+  }
+
+  // Maps the ticked audio streams, amerging them in `filters` if there's more than one
+  fn audio_args(&self, filters: &mut Vec<String>, duration: f64) -> Vec<OsString> {
+    let fade = self.audio_filter(duration);
+    let selected = self.selected_audio();
+    if self.audio_encoder() == Some("copy") && selected.len() > 1 {
+      // Copies can't be merged, so each stream is kept as its own track
+      return selected.iter().flat_map(|i| ["-map".into(), format!("0:a:{}", i).into()]).collect();
+    }
+    if !self.segments.is_empty() {
+      // segment_filter already merged them into [sa]
+      if selected.is_empty() {
+        return vec!["-an".into()];
+      }
+      let map = match fade {
+        Some(fade) => {
+          filters.push(format!("[sa]{}[a]", fade));
+          "[a]"
+        },
+        None => "[sa]",
+      };
+      return vec!["-map".into(), map.into()];
+    }
+    let map = match selected[..] {
+      // Probing failed, so just take whatever audio there is
+      [] if self.audio_streams.is_empty() => "0:a?".to_owned(),
+      [] => return vec!["-an".into()],
+      [stream] => format!("0:a:{}", stream),
+      ref streams => {
+        let inputs: String = streams.iter().map(|i| format!("[0:a:{}]", i)).collect();
+        let fade = fade.map(|fade| format!(",{}", fade)).unwrap_or_default();
+        filters.push(format!("{}amerge=inputs={}{}[a]", inputs, streams.len(), fade));
+        return vec!["-map".into(), "[a]".into()];
+      },
+    };
+    let mut args: Vec<OsString> = vec!["-map".into(), map.into()];
+    if let Some(fade) = fade {
+      args.push("-af".into());
+      args.push(fade.into());
+    }
+    args
+  }
+
+  fn default_file_name(&self) -> String {
+    let stem = if self.format.is_audio_only() { "audio" } else { "video" };
+    format!("{}.{}", stem, self.format.extension())
+  }
+
+  // Starts in the pinned output folder, or wherever the last output went
+  fn output_dialog(&self) -> FileDialog {
+    let dialog = FileDialog::new();
+    match self.output_dir.as_ref().or(self.last_output_dir.as_ref()).filter(|dir| dir.is_dir()) {
+      Some(dir) => dialog.set_directory(dir),
+      None => dialog,
+    }
+  }
+
+  fn remember_output_dir(&mut self, path: Option<PathBuf>) -> Option<PathBuf> {
+    if let Some(dir) = path.as_ref().and_then(|path| path.parent()) {
+      self.last_output_dir = Some(dir.to_path_buf());
+    }
+    path
+  }
+
+  fn save_dialog(&mut self) -> Option<PathBuf> {
+    let path = self.output_dialog()
+      .add_filter(self.format.name(), &[self.format.extension()])
+      .set_file_name(self.default_file_name())
+      .save_file();
+    self.remember_output_dir(path)
+  }
+
+  // What `snip()` would run right now, with a placeholder output name
+  fn command_preview(&self) -> Option<String> {
+    let job = self.build_job(PathBuf::from(self.default_file_name()))?;
+    let ffmpeg = shell_quote(&self.ffmpeg_path.to_string_lossy());
+    let commands: Vec<String> = job.passes.iter()
+      .map(|args| [ffmpeg.clone()].into_iter().chain(args.iter().map(|a| shell_quote(&a.to_string_lossy()))).collect::<Vec<_>>().join(" "))
+      .collect();
+    Some(commands.join(" && "))
+  }
+
+  fn build_job(&self, new: PathBuf) -> Option<SnipJob> {
+    let (Some(file), Some(player)) = (self.file_path.clone(), self.player.as_ref()) else {
+      return None;
+    };
+    self.build_job_for(file, player.duration_ms, new)
+  }
+
+  fn build_job_for(&self, file: PathBuf, duration_ms: i64, new: PathBuf) -> Option<SnipJob> {
+    let mut job = self.build_job_with(file.clone(), duration_ms, new.clone(), &self.encoder)?;
+    if is_hardware_encoder(&self.encoder) && !self.copy_streams && self.format.video_codec() == Some("libx264") {
+      job.fallback = self.build_job_with(file, duration_ms, new, "libx264").map(|job| Fallback {
+        passes: job.passes,
+        note: format!("{} failed, retrying with libx264", self.encoder),
+      });
+    } else if self.copy_streams && self.format.is_video() {
+      let passes = vec![self.reencode_args(&job.source, &new, job.duration)];
+      if self.copy_fallback {
+        job.fallback = Some(Fallback {
+          passes: passes.clone(),
+          note: "copying the streams failed, re-encoding instead".to_owned(),
+        });
+      }
+      job.reencode = Some(passes);
+    }
+    Some(job)
+  }
+
+  // A plain re-encode of the selection, for when the container rejects a stream copy
+  fn reencode_args(&self, file: &Path, new: &Path, duration: f64) -> Vec<OsString> {
+    let mut args = self.input_args(file);
+    args.extend(self.seek_args());
+    args.push("-map".into());
+    args.push(format!("0:v:{}", self.video_stream).into());
+    args.push("-map".into());
+    args.push("0:a?".into());
+    if let Some(codec) = self.format.video_codec() {
+      args.push("-c:v".into());
+      args.push(codec.into());
+      if codec == "libx264" {
+        args.extend(["-crf".into(), self.crf.to_string().into(), "-preset".into(), self.preset.clone().into()]);
+      }
+    }
+    if let Some(codec) = self.format.audio_codec() {
+      args.push("-c:a".into());
+      args.push(codec.into());
+    }
+    if self.copy_metadata {
+      args.push("-map_metadata".into());
+      args.push("0".into());
+    }
+    args.push(new.into());
+    for (format, path) in self.extra_outputs(new) {
+      args.extend(self.extra_output_args(format, &path, duration));
+    }
+    args
+  }
+
+  // Segments are cut in the one filtergraph, so they only go to the main output,
+  // and so does merged audio, since extra outputs only map a single stream.
+  // Audio-only outputs are left out with no audio stream to put in them
+  fn extra_outputs(&self, new: &Path) -> Vec<(OutputFormat, PathBuf)> {
+    if !self.segments.is_empty() {
+      return vec![];
+    }
+    let selected = self.selected_audio().len();
+    self.extra_formats.iter()
+      .filter(|format| **format != self.format && !(selected > 1 && format.has_audio()) && !(selected == 0 && format.is_audio_only()))
+      .map(|format| (*format, new.with_extension(format.extension())))
+      .collect()
+  }
+
+  // Another output from the same decode, with the format's own codecs and no size target
+  fn extra_output_args(&self, format: OutputFormat, new: &Path, duration: f64) -> Vec<OsString> {
+    let mut args = self.seek_args();
+    let filter = self.video_filter(duration);
+    if format == OutputFormat::Gif {
+      let filter = filter.map(|filter| format!("{},", filter)).unwrap_or_default();
+      args.push("-map".into());
+      args.push(format!("0:v:{}", self.video_stream).into());
+      args.push("-vf".into());
+      args.push(format!("{}split[a][b];[a]palettegen[p];[b][p]paletteuse", filter).into());
+    } else if format.is_audio_only() {
+      args.push("-vn".into());
+    } else {
+      args.push("-map".into());
+      args.push(format!("0:v:{}", self.video_stream).into());
+      if let Some(filter) = filter {
+        args.push("-vf".into());
+        args.push(filter.into());
+      }
+      if let Some(codec) = format.video_codec() {
+        args.push("-c:v".into());
+        args.push(codec.into());
+        if codec == "libx264" {
+          args.extend(["-crf".into(), self.crf.to_string().into(), "-preset".into(), self.preset.clone().into()]);
+        }
+      }
+    }
+    match self.selected_audio().first() {
+      Some(stream) if format.has_audio() => {
+        args.push("-map".into());
+        args.push(format!("0:a:{}", stream).into());
+        if let Some(codec) = format.audio_codec() {
+          args.push("-c:a".into());
+          args.push(codec.into());
+        }
+        if let Some(filter) = self.audio_filter(duration) {
+          args.push("-af".into());
+          args.push(filter.into());
+        }
+      },
+      _ => args.push("-an".into()),
+    }
+    if self.copy_metadata {
+      args.push("-map_metadata".into());
+      args.push("0".into());
+    }
+    args.push(new.into());
+    args
+  }
+
+  fn build_job_with(&self, file: PathBuf, duration_ms: i64, new: PathBuf, encoder: &str) -> Option<SnipJob> {
+    let format = self.format;
+
+    let duration = self.clip_duration(duration_ms);
+    if duration <= 0. {
+      return None;
+    }
+    let sized = self.target_size && !self.copy_streams && format.is_video();
+
+    let mut args = self.input_args(&file);
+    let mut first_pass = None;
+    let mut cleanup = vec![];
+    let mut temp_files = vec![];
+    let subtitle_codec = self.subtitle_codec();
+    if subtitle_codec.is_some() {
+      let srt = self.scratch_dir().join(format!("snip-caption-{}.srt", std::process::id()));
+      args.push("-i".into());
+      args.push(srt.as_os_str().into());
+      let offset = self.filter_offset() as i64;
+      temp_files.push((srt.clone(), self.caption.srt(offset, offset + duration as i64)));
+      cleanup.push(srt);
+    }
+    args.extend(self.seek_args());
+    if self.copy_streams && format.is_video() {
+      args.push("-c".into());
+      args.push("copy".into());
+      args.push("-map".into());
+      args.push("0".into());
+    } else if format == OutputFormat::Gif {
+      args.push("-filter_complex".into());
+      let filter = self.video_filter(duration).map(|filter| format!("{},", filter)).unwrap_or_default();
+      let graph = format!("{}split[a][b];[a]palettegen[p];[b][p]paletteuse", filter);
+      args.push(match self.segment_filter(true, &[]) {
+        Some(segments) => format!("{};[sv]{}", segments, graph),
+        None => format!("[0:v:{}]{}", self.video_stream, graph),
+      }.into());
+      args.push("-an".into());
+    } else if format.is_audio_only() {
+      args.push("-vn".into());
+      let mut filters: Vec<String> = self.segment_filter(false, &self.selected_audio()).into_iter().collect();
+      let audio = self.audio_args(&mut filters, duration);
+      if !filters.is_empty() {
+        args.push("-filter_complex".into());
+        args.push(filters.join(";").into());
+      }
+      args.extend(audio);
+      args.extend(self.audio_codec_args());
+    } else {
+      let codec = match format.video_codec() {
+        Some("libx264") | None => encoder,
+        Some(codec) => codec,
+      };
+      // Hardware encoders don't do x264-style two-pass, so they just get a bitrate
+      let two_pass = sized && !is_hardware_encoder(codec);
+      let mut video: Vec<OsString> = vec!["-c:v".into(), codec.into()];
+      if sized {
+        let bitrate = target_bitrate_kbps(self.target_size_mb, duration, self.audio_kbps);
+        video.push("-b:v".into());
+        video.push(format!("{}k", bitrate).into());
+      } else if codec == "libx264" {
+        video.push("-crf".into());
+        video.push(self.crf.to_string().into());
+      }
+      if codec == "libx264" {
+        video.push("-preset".into());
+        video.push(self.preset.clone().into());
+      }
+      if two_pass {
+        let log = self.scratch_dir().join(format!("snip-ffmpeg2pass-{}", std::process::id()));
+        video.push("-passlogfile".into());
+        video.push(log.as_os_str().into());
+
+        let mut first = self.input_args(&file);
+        first.extend(self.seek_args());
+        let mut filters: Vec<String> = self.segment_filter(true, &[]).into_iter().collect();
+        let map = self.video_map(&mut filters, duration);
+        if !filters.is_empty() {
+          first.push("-filter_complex".into());
+          first.push(filters.join(";").into());
+        }
+        first.push("-map".into());
+        first.push(map.into());
+        first.extend(video.iter().cloned());
+        first.extend(["-pass", "1", "-an", "-f", "null", NULL_OUTPUT].map(OsString::from));
+        first_pass = Some(first);
+
+        video.push("-pass".into());
+        video.push("2".into());
+        for suffix in ["-0.log", "-0.log.mbtree"] {
+          let mut path = log.clone().into_os_string();
+          path.push(suffix);
+          cleanup.push(PathBuf::from(path));
+        }
+      }
+      args.extend(video);
+      args.extend(self.audio_codec_args());
+      let audio = if format.has_audio() { self.selected_audio() } else { vec![] };
+      let mut filters: Vec<String> = self.segment_filter(true, &audio).into_iter().collect();
+      let video = self.video_map(&mut filters, duration);
+      let audio = self.audio_args(&mut filters, duration);
+      if !filters.is_empty() {
+        args.push("-filter_complex".into());
+        args.push(filters.join(";").into());
+      }
+      args.push("-map".into());
+      args.push(video.into());
+      args.extend(audio);
+      if let Some(codec) = subtitle_codec {
+        args.extend(["-map", "1:0", "-c:s", codec].map(OsString::from));
+      }
+    }
+
+    if self.copy_metadata {
+      args.push("-map_metadata".into());
+      args.push("0".into());
+    }
+
+    args.push(new.as_os_str().into());
+
+    let extra_outputs = self.extra_outputs(&new);
+    for (format, path) in &extra_outputs {
+      args.extend(self.extra_output_args(*format, path, duration));
+    }
+
+    let passes = first_pass.into_iter().chain([args]).collect();
+    Some(SnipJob {
+      source: file,
+      output: new,
+      extra_outputs: extra_outputs.into_iter().map(|(_, path)| path).collect(),
+      start: self.start,
+      end: self.end,
+      passes,
+      kind: JobKind::of(format),
+      duration,
+      cleanup,
+      temp_files,
+      copy_metadata: self.copy_metadata,
+      status: JobStatus::Pending,
+      fallback: None,
+      reencode: None,
+      concat: false,
+    })
+  }
+
+  fn run_job(&mut self, job: &SnipJob) -> bool {
+    for (path, contents) in &job.temp_files {
+      if let Err(err) = fs::write(path, contents) {
+        self.ffmpeg_error = Some(format!("couldn't write `{}`: {}", path.display(), err));
+        return false;
+      }
+    }
+    // Goes through `submit` first, except for retries and re-exports that only replace the job's own output
+    for output in [&job.output].into_iter().chain(&job.extra_outputs).filter(|output| output.exists()) {
+      if let Err(err) = remove_with_retry(output) {
+        self.ffmpeg_error = Some(format!("couldn't replace `{}`: {} (close any program that has it open or pick another name)", output.display(), err));
+        self.toasts.error("Output file is in use");
+        return false;
+      }
+    }
+    let on_success = {
+      let copy_metadata = job.copy_metadata;
+      let source = job.source.clone();
+      let output = job.output.clone();
+      move || {
+        if copy_metadata {
+          let _ = copy_modified_time(&source, &output);
+        }
+      }
+    };
+    self.start_log(&job.output);
+    if let Err(err) = self.job.start(&self.ffmpeg_path, job.passes.clone(), job.kind, job.duration, job.cleanup.clone(), on_success) {
+      self.ffmpeg_error = Some(format!("failed to run ffmpeg at `{}`: {}", self.ffmpeg_path.display(), err));
+      self.toasts.error("ffmpeg failed to start");
+      return false;
+    }
+    self.was_in_progress = true;
+    self.output = Some(job.output.clone());
+    self.frames_before = None;
+    self.running = Some(job.clone());
+    self.trim_mismatch = None;
+    true
+  }
+
+  fn submit(&mut self, job: SnipJob, queue: bool) {
+    let exists = job.output.exists() || job.extra_outputs.iter().any(|output| output.exists());
+    if exists || (queue && self.queue.iter().any(|queued| queued.output == job.output)) {
+      self.overwrite = Some((job, queue));
+    } else if queue {
+      self.queue.push(job);
+    } else {
+      self.run_job(&job);
+    }
+  }
+
+  // Checked before asking where to save, since build_job would just come back empty
+  fn has_something_to_export(&mut self) -> bool {
+    let Some(player) = &self.player else {
+      return false;
+    };
+    if self.clip_duration(player.duration_ms) > 0. {
+      return true;
+    }
+    match (self.start, self.end) {
+      (Some(start), Some(end)) if start > end => self.toasts.error("Start is after end"),
+      _ => self.toasts.error("Nothing is selected"),
+    }
+    false
+  }
+
+  pub fn snip(&mut self) {
+    if !self.has_something_to_export() {
+      return;
+    }
+    if let Some(job) = self.save_dialog().and_then(|new| self.build_job(new)) {
+      if self.confirm_export {
+        self.confirm = Some((job, false));
       } else {
-        args.push("-map".to_owned());
-        args.push("0".to_owned());
+        self.submit(job, false);
       }
-      
-      args.push(new.to_str().unwrap().to_string());
+    }
+  }
 
-      let in_progress = self.in_progress.clone();
-      let progress = self.progress.clone();
+  // Exact for a target size or a fixed bitrate, a guess from the frame size for CRF
+  fn estimated_size_mb(&self) -> Option<f64> {
+    let player = self.player.as_ref()?;
+    let seconds = self.clip_duration(player.duration_ms) / 1000.;
+    let audio_kbps = match self.audio_encoder() {
+      Some("copy") | None => 0.,
+      // 16-bit stereo at 48kHz
+      Some("pcm_s16le") => 1536.,
+      Some(_) => self.audio_kbps as f64,
+    };
+    let kbps = if self.format == OutputFormat::Gif {
+      return None;
+    } else if self.format.is_audio_only() {
+      audio_kbps
+    } else if self.copy_streams {
+      self.info.as_ref()?.bitrate? as f64 / 1000.
+    } else if self.target_size {
+      return Some(self.target_size_mb);
+    } else {
+      let (w, h) = self.output_size()?;
+      let source = if self.fps > 0. { self.fps } else { 30. };
+      let fps = if self.output_fps > 0. { self.output_fps.min(source) } else { source };
+      w as f64 * h as f64 * fps * crf_bits_per_pixel(self.crf) / 1000. + audio_kbps
+    };
+    Some(kbps * 1000. * seconds / 8. / 1024. / 1024.)
+  }
 
-      let duration = (self.end.unwrap_or(self.player.as_ref().unwrap().duration_ms) - self.start.unwrap_or(0)) as f64;
+  // Frame size after cropping and scaling
+  fn output_size(&self) -> Option<(u32, u32)> {
+    let (w, h) = match self.crop.bounds() {
+      Some((w, h, _, _)) => (w, h),
+      None => self.info.as_ref()?.resolution?,
+    };
+    match (self.resolution.size(), self.resolution.height()) {
+      (Some(size), _) if self.letterbox => Some(size),
+      (_, Some(height)) => Some((w * height / h.max(1), height)),
+      _ => Some((w, h)),
+    }
+  }
 
-      let handle = std::thread::spawn(move || {
-        let mut ffmpeg = std::process::Command::new("ffmpeg")
-          .args(args)
-          .stdin(Stdio::null())
-          .stdout(Stdio::null())
-          .stderr(Stdio::piped())
-          .spawn()
+  pub fn add_to_queue(&mut self) {
+    if !self.has_something_to_export() {
+      return;
+    }
+    if let Some(job) = self.save_dialog().and_then(|new| self.build_job(new)) {
+      self.submit(job, true);
+    }
+  }
+
+  // Queues an export of every batch file into one folder, with this file's
+  // settings and either the same start/end or the same amount cut off the ends
+  pub fn queue_batch(&mut self) {
+    if self.batch.files.is_empty() || !self.segments.is_empty() {
+      return;
+    }
+    let Some(dir) = self.output_dialog().pick_folder() else {
+      return;
+    };
+    self.last_output_dir = Some(dir.clone());
+    let current = (self.file_path.take(), self.start, self.end);
+    let mut queued = 0;
+    let mut skipped = vec![];
+    for file in self.batch.files.clone() {
+      let duration_ms = probe::probe_info(&self.ffmpeg_path, &file)
+        .and_then(|info| info.duration)
+        .map(|d| (d * 1000.) as i64);
+      let Some((duration_ms, (start, end))) = duration_ms.and_then(|d| Some((d, self.batch.range(d, current.1, current.2)?))) else {
+        skipped.push(file.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        continue;
+      };
+      self.file_path = Some(file.clone());
+      self.start = start;
+      self.end = end;
+      let stem = file.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+      let taken = |path: &Path| path.exists() || self.queue.iter().any(|job| job.output == path);
+      let mut new = dir.join(format!("{}.{}", stem, self.format.extension()));
+      if taken(&new) {
+        new = (1..)
+          .map(|n| dir.join(format!("{} ({}).{}", stem, n, self.format.extension())))
+          .find(|path| !taken(path))
           .unwrap();
+      }
+      match self.build_job_for(file, duration_ms, new) {
+        Some(job) => {
+          self.queue.push(job);
+          queued += 1;
+        },
+        None => skipped.push(stem),
+      }
+    }
+    (self.file_path, self.start, self.end) = current;
+    if queued > 0 {
+      self.toasts.info(format!("Queued {} files", queued));
+    }
+    if !skipped.is_empty() {
+      self.toasts.error(format!("Nothing to export from {}", skipped.join(", ")));
+    }
+  }
 
-        let re = Regex::new(r"frame=.+time=(\d+):(\d+):(\d+).(\d+)").unwrap();
+  pub fn add_clip(&mut self) {
+    let (Some(path), Some(player)) = (self.file_path.clone(), self.player.as_ref()) else {
+      return;
+    };
+    self.clips.push(Clip {
+      path,
+      start: self.start,
+      end: self.end,
+      duration_ms: player.duration_ms,
+      size: self.info.as_ref().and_then(|info| info.resolution),
+      // Without probe results it's assumed there is some
+      has_audio: self.info.as_ref().is_none_or(|info| !info.audio_codecs.is_empty()),
+    });
+  }
 
-        {
-          *in_progress.lock().unwrap() = true;
-        }
-        if let Some(mut stderr) = ffmpeg.stderr.take() {
-          let mut a = [0u8; 256];
-          while let Ok(n) = stderr.read(&mut a) {
-            if n == 0 {
-              break
-            } else {
-              let s = String::from_utf8(a.to_vec()).unwrap();
-              if let Some(caps) = re.captures(&s) {
-                let processed = {
-                  let h = caps.get(1).unwrap().as_str().parse::<i64>().unwrap();
-                  let m = caps.get(2).unwrap().as_str().parse::<i64>().unwrap();
-                  let s = caps.get(3).unwrap().as_str().parse::<i64>().unwrap();
-                  let ms = caps.get(4).unwrap().as_str().parse::<i64>().unwrap() * 10;
-                  h * 3600000 + m * 60000 + s * 1000 + ms
-                } as f64;
-                *progress.lock().unwrap() = processed / duration;
-              }
-            }
-          }
+  fn build_concat(&self, new: PathBuf) -> SnipJob {
+    SnipJob {
+      source: self.clips[0].path.clone(),
+      extra_outputs: vec![],
+      passes: vec![concat_args(&self.clips, self.format, &new)],
+      kind: JobKind::Video,
+      output: new,
+      start: None,
+      end: None,
+      duration: self.clips.iter().map(|clip| clip.length()).sum::<i64>() as f64,
+      cleanup: vec![],
+      temp_files: vec![],
+      copy_metadata: false,
+      status: JobStatus::Pending,
+      fallback: None,
+      reencode: None,
+      concat: true,
+    }
+  }
+
+  pub fn concat(&mut self) {
+    if self.clips.len() < 2 || !self.format.is_video() {
+      return;
+    }
+    if let Some(new) = self.save_dialog() {
+      let job = self.build_concat(new);
+      self.submit(job, false);
+    }
+  }
+
+  fn job_finished(&mut self) {
+    let succeeded = self.job.succeeded();
+    if !succeeded {
+      if let Some(mut job) = self.running.take().filter(|job| job.fallback.is_some()) {
+        let fallback = job.fallback.take().unwrap();
+        job.passes = fallback.passes;
+        self.toasts.info(fallback.note);
+        if self.run_job(&job) {
+          return;
         }
-        *in_progress.lock().unwrap() = false;
+      }
+    }
+    let Some(output) = &self.output else {
+      return;
+    };
+    if let Some(job) = self.running.as_ref().filter(|job| succeeded && job.reencode.is_some()) {
+      let actual = probe::probe_info(&self.ffmpeg_path, output).and_then(|info| info.duration).map(|d| d * 1000.);
+      if let Some(actual) = actual.filter(|actual| (actual - job.duration).abs() > TRIM_TOLERANCE_MS) {
+        self.trim_mismatch = Some((job.clone(), actual));
+      }
+    }
+    let extras = self.running.as_ref().map(|job| job.extra_outputs.clone()).unwrap_or_default();
+    let name = [output].into_iter().chain(&extras)
+      .map(|path| path.file_name().unwrap_or_default().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join(", ");
+    if succeeded {
+      self.toasts.info(format!("Saved {}", name));
+    } else if let Some(err) = self.job.error() {
+      self.toasts.error(format!("ffmpeg failed: {}", err.summary));
+    }
+    if self.notify {
+      let body = if succeeded { format!("Saved {}", name) } else { format!("Failed to save {}", name) };
+      std::thread::spawn(move || {
+        let _ = Notification::new().summary("snip").body(&body).show();
       });
-      self.ffmpeg_handle = Some(handle);
+    }
+    if succeeded && self.reveal_output {
+      let _ = reveal(output);
+    }
+  }
+
+  fn process_queue(&mut self) {
+    if !self.processing_queue || self.job.in_progress() {
+      return;
+    }
+    if let Some(running) = self.queue.iter_mut().find(|job| job.status == JobStatus::Running) {
+      running.status = if self.job.succeeded() { JobStatus::Done } else { JobStatus::Failed };
+    }
+    match self.queue.iter().position(|job| job.status == JobStatus::Pending) {
+      Some(i) => {
+        let job = self.queue[i].clone();
+        self.queue[i].status = if self.run_job(&job) { JobStatus::Running } else { JobStatus::Failed };
+      },
+      None => self.processing_queue = false,
     }
   }
 
-  fn new(path: PathBuf) -> Self {
-    Self {
-      audio_device: AudioDevice::new().unwrap(),
-      file_path: path,
+  pub fn screenshot(&mut self) {
+    let (Some(file), Some(player)) = (self.file_path.clone(), self.player.as_ref()) else {
+      return;
+    };
+    let elapsed = player.elapsed_ms();
+    let new = self.output_dialog()
+      .add_filter("PNG", &["png"])
+      .set_file_name(format!("frame-{}.png", format_ms(elapsed).replace(':', "-")))
+      .save_file();
+    if let Some(new) = self.remember_output_dir(new) {
+      if new.exists() {
+        if let Err(err) = remove_with_retry(&new) {
+          self.ffmpeg_error = Some(format!("couldn't replace `{}`: {} (close any program that has it open or pick another name)", new.display(), err));
+          self.toasts.error("Output file is in use");
+          return;
+        }
+      }
+
+      let args: Vec<OsString> = vec![
+        "-ss".into(),
+        format_ms(elapsed).into(),
+        "-i".into(),
+        file.into(),
+        "-frames:v".into(),
+        "1".into(),
+        // Otherwise a % in the name is taken as part of an image sequence pattern
+        "-update".into(),
+        "1".into(),
+        new.clone().into(),
+      ];
+
+      self.start_log(&new);
+      if let Err(err) = self.job.start(&self.ffmpeg_path, vec![args], JobKind::Screenshot, 1., vec![], || {}) {
+        self.ffmpeg_error = Some(format!("failed to run ffmpeg at `{}`: {}", self.ffmpeg_path.display(), err));
+        return;
+      }
+      self.was_in_progress = true;
+      self.running = None;
+      self.output = Some(new);
+      self.frames_before = None;
+    }
+  }
+
+  // The selection as numbered pngs in a folder, thinned to `frames_fps` if it's set
+  pub fn export_frames(&mut self) {
+    let (Some(file), Some(player)) = (self.file_path.clone(), self.player.as_ref()) else {
+      return;
+    };
+    let duration = self.clip_duration(player.duration_ms);
+    if duration <= 0. {
+      return;
+    }
+    let Some(dir) = self.output_dialog().set_title("Export frames to").pick_folder() else {
+      return;
+    };
+    self.last_output_dir = Some(dir.clone());
+
+    let mut args = self.input_args(&file);
+    args.extend(self.seek_args());
+    let mut filters: Vec<String> = self.segment_filter(true, &[]).into_iter().collect();
+    let mut map = self.video_map(&mut filters, duration);
+    if self.frames_fps > 0. {
+      let input = if map.starts_with('[') { map } else { format!("[{}]", map) };
+      filters.push(format!("{}fps={}[f]", input, self.frames_fps));
+      map = "[f]".to_owned();
+    }
+    if !filters.is_empty() {
+      args.push("-filter_complex".into());
+      args.push(filters.join(";").into());
+    }
+    args.push("-map".into());
+    args.push(map.into());
+    // image2 takes a % anywhere in the path as part of the pattern
+    let escaped = match dir.to_str() {
+      Some(name) if name.contains('%') => PathBuf::from(name.replace('%', "%%")),
+      _ => dir.clone(),
+    };
+    args.push(escaped.join("frame_%05d.png").into());
+
+    let before = frame_files(&dir);
+    self.start_log(&dir);
+    if let Err(err) = self.job.start(&self.ffmpeg_path, vec![args], JobKind::Frames, duration, vec![], || {}) {
+      self.ffmpeg_error = Some(format!("failed to run ffmpeg at `{}`: {}", self.ffmpeg_path.display(), err));
+      return;
+    }
+    self.was_in_progress = true;
+    self.running = None;
+    self.output = Some(dir);
+    self.frames_before = Some(before);
+  }
+
+  pub fn cancel(&mut self) {
+    self.job.cancel();
+    self.was_in_progress = false;
+    self.processing_queue = false;
+    if let Some(running) = self.queue.iter_mut().find(|job| job.status == JobStatus::Running) {
+      running.status = JobStatus::Failed;
+    }
+    let mut partial = self.running.take().map(|job| job.extra_outputs).unwrap_or_default();
+    if let Some(output) = self.output.take() {
+      match self.frames_before.take() {
+        Some(before) => partial.extend(frame_files(&output).into_iter().filter(|frame| !before.contains(frame))),
+        None => partial.push(output),
+      }
+    }
+    let stuck = partial.iter()
+      .filter(|path| remove_with_retry(path).is_err_and(|err| err.kind() != io::ErrorKind::NotFound))
+      .count();
+    if stuck > 0 {
+      self.toasts.error(format!("Couldn't remove {} partly written file(s)", stuck));
+    }
+  }
+
+  fn position(&self) -> Option<(PathBuf, i64)> {
+    let (Some(path), Some(player)) = (&self.file_path, &self.player) else {
+      return None;
+    };
+    Some((path.clone(), player.elapsed_ms()))
+  }
+
+  fn audio_layout(&self) -> Option<(String, Vec<bool>)> {
+    (self.audio_streams.len() > 1).then(|| (probe::audio_layout(&self.audio_streams), self.audio_selected.clone()))
+  }
+
+  pub fn open(&mut self, path: PathBuf) {
+    if let Some((path, elapsed)) = self.position() {
+      self.positions.insert(path, elapsed);
+    }
+    if let Some((layout, selected)) = self.audio_layout() {
+      self.audio_layouts.insert(layout, selected);
+    }
+    if let Some(file) = self.file_path.clone().filter(|_| !self.markers.is_empty()) {
+      self.saved_markers.insert(file, std::mem::take(&mut self.markers));
+    }
+    self.markers = self.saved_markers.remove(&path).unwrap_or_default();
+    if let Some(mut player) = self.player.take() {
+      player.stop_direct();
+    }
+    self.player_error = None;
+    self.waveform = Waveform::load(&self.ffmpeg_path, &path, &self.scratch_dir());
+    if let Some(cache) = self.waveform.cache_file().filter(|cache| !self.waveform_caches.iter().any(|c| c == cache)) {
+      self.waveform_caches.push(cache.to_path_buf());
+    }
+    self.keyframes = Keyframes::load(&self.ffmpeg_path, &path);
+    self.timeline = Timeline::default();
+    self.thumbnails = Thumbnails::default();
+    self.boundaries = Boundaries::default();
+    self.gif_preview.clear();
+    self.silence.clear();
+    self.crop.reset();
+    self.speed = PlaybackSpeed::default();
+    let probed = probe::probe(&self.ffmpeg_path, &path);
+    let (fps, video_streams, audio_streams, info) = match probed {
+      Some(probed) => (probed.fps, probed.video_streams, probed.audio_streams, Some(probed.info)),
+      None => (None, vec![], vec![], None),
+    };
+    self.fps = fps.unwrap_or(0.);
+    self.video_streams = video_streams;
+    self.video_stream = 0;
+    self.audio_streams = audio_streams;
+    self.audio_selected = self.audio_layouts.get(&probe::audio_layout(&self.audio_streams))
+      .filter(|selected| selected.len() == self.audio_streams.len() && self.audio_streams.len() > 1)
+      .cloned()
+      .unwrap_or_else(|| vec![true; self.audio_streams.len()]);
+    self.audio_stream = 0;
+    self.info = info;
+    self.start = None;
+    self.end = None;
+    self.end_clamped = None;
+    self.segments.clear();
+    self.history = History::default();
+    self.loop_selection = false;
+    self.add_recent(&path);
+    self.file_path = Some(path);
+  }
+
+  fn settings(&self) -> Settings {
+    Settings {
+      ffmpeg_path: self.ffmpeg_path.clone(),
+      temp_dir: self.temp_dir.clone(),
+      recent_files: self.recent_files.clone(),
+      positions: self.positions.clone().into_iter().chain(self.position()).collect(),
+      markers: self.saved_markers.clone().into_iter()
+        .chain(self.file_path.clone().filter(|_| !self.markers.is_empty()).map(|path| (path, self.markers.clone())))
+        .collect(),
+      audio_layouts: self.audio_layouts.clone().into_iter().chain(self.audio_layout()).collect(),
+      volume: self.volume,
+      seek_step_ms: self.seek_step_ms,
+      seek_step_fine_ms: self.seek_step_fine_ms,
+      quick_clip_ms: self.quick_clip_ms,
+      silence_db: self.silence_db,
+      silence_ms: self.silence_ms,
+      frames_fps: self.frames_fps,
+      copy_streams: self.copy_streams,
+      copy_fallback: self.copy_fallback,
+      format: self.format,
+      extra_formats: self.extra_formats.clone(),
+      resolution: self.resolution,
+      output_fps: self.output_fps,
+      letterbox: self.letterbox,
+      pad_color: self.pad_color,
+      encoder: self.encoder.clone(),
+      crf: self.crf,
+      preset: self.preset.clone(),
+      target_size: self.target_size,
+      target_size_mb: self.target_size_mb,
+      copy_metadata: self.copy_metadata,
+      normalize_audio: self.normalize_audio,
+      audio_codec: self.audio_codec,
+      audio_kbps: self.audio_kbps,
+      fast_seek: self.fast_seek,
+      snap_to_keyframe: self.snap_to_keyframe,
+      show_keyframes: self.show_keyframes,
+      reveal_output: self.reveal_output,
+      last_output_dir: self.last_output_dir.clone(),
+      output_dir: self.output_dir.clone(),
+      notify: self.notify,
+      confirm_export: self.confirm_export,
+      keybinds: self.keybinds.keys(),
+      light_theme: self.light_theme,
+      mini: self.mini,
+      preview_size: self.preview_size,
+      preview_zoom: self.preview_zoom,
+    }
+  }
+
+  fn add_recent(&mut self, path: &Path) {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    self.recent_files.retain(|p| *p != path);
+    self.recent_files.insert(0, path);
+    self.recent_files.truncate(MAX_RECENT_FILES);
+  }
+
+  fn current_preset(&self, name: String) -> Preset {
+    Preset {
+      name,
+      format: self.format,
+      resolution: self.resolution,
+      letterbox: self.letterbox,
+      encoder: self.encoder.clone(),
+      crf: self.crf,
+      x264_preset: self.preset.clone(),
+      target_size: self.target_size,
+      target_size_mb: self.target_size_mb,
+      audio_codec: self.audio_codec,
+      audio_kbps: self.audio_kbps,
+    }
+  }
+
+  fn apply_preset(&mut self, preset: &Preset) {
+    self.format = preset.format;
+    self.resolution = preset.resolution;
+    self.letterbox = preset.letterbox;
+    self.encoder = preset.encoder.clone();
+    self.crf = preset.crf;
+    self.preset = preset.x264_preset.clone();
+    self.target_size = preset.target_size;
+    self.target_size_mb = preset.target_size_mb;
+    self.audio_codec = preset.audio_codec;
+    self.audio_kbps = preset.audio_kbps;
+    self.preset_name = preset.name.clone();
+  }
+
+  // Saving under an existing name replaces that preset
+  fn save_preset(&mut self) {
+    let name = self.preset_name.trim().to_owned();
+    let preset = self.current_preset(name.clone());
+    match self.presets.iter_mut().find(|p| p.name == name) {
+      Some(existing) => *existing = preset,
+      None => self.presets.push(preset),
+    }
+    self.preset_error = preset::save(&self.presets).err();
+  }
+
+  fn delete_preset(&mut self) {
+    self.presets.retain(|p| p.name != self.preset_name.trim());
+    self.preset_error = preset::save(&self.presets).err();
+  }
+
+  fn new(cc: &eframe::CreationContext, files: Vec<PathBuf>) -> Self {
+    let settings: Settings = cc.storage
+      .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY))
+      .unwrap_or_default();
+    let encoders = probe::available_encoders(&settings.ffmpeg_path, &H264_ENCODERS);
+    let mut app = Self {
+      audio_device: AudioDevice::new().map_err(|err| err.to_string()),
+      ffmpeg_error: check_ffmpeg(&settings.ffmpeg_path),
+      waveform: Waveform::default(),
+      waveform_caches: vec![],
+      keyframes: Keyframes::default(),
+      snap_to_keyframe: settings.snap_to_keyframe,
+      show_keyframes: settings.show_keyframes,
+      timeline: Timeline::default(),
+      thumbnails: Thumbnails::default(),
+      boundaries: Boundaries::default(),
+      gif_preview: GifPreview::default(),
+      crop: Crop::default(),
+      fps: 0.,
+      video_streams: vec![],
+      video_stream: 0,
+      audio_streams: vec![],
+      audio_selected: vec![],
+      audio_layouts: settings.audio_layouts,
+      info: None,
+      audio_stream: 0,
+      ffmpeg_path: settings.ffmpeg_path,
+      temp_dir: settings.temp_dir,
+      file_path: None,
+      recent_files: settings.recent_files,
+      up_next: vec![],
+      positions: settings.positions.into_iter().filter(|(path, _)| path.exists()).collect(),
+      markers: vec![],
+      saved_markers: settings.markers.into_iter().filter(|(path, _)| path.exists()).collect(),
+      marker_label: String::new(),
       player: None,
+      player_error: None,
+      player_loading: false,
+      volume: settings.volume,
+      muted: false,
+      speed: PlaybackSpeed::default(),
+      scroll: 0.,
+      seek_step_ms: settings.seek_step_ms,
+      seek_step_fine_ms: settings.seek_step_fine_ms,
+      quick_clip_ms: settings.quick_clip_ms,
+      silence: Silence::default(),
+      silence_db: settings.silence_db,
+      silence_ms: settings.silence_ms,
+      frames_fps: settings.frames_fps,
+      loop_selection: false,
       start: None,
       end: None,
-      audio_merge: 1,
-      merge_or_map: true,
+      segments: vec![],
+      start_text: String::new(),
+      end_text: String::new(),
+      end_clamped: None,
+      history: History::default(),
+      copy_streams: settings.copy_streams,
+      copy_fallback: settings.copy_fallback,
+      format: settings.format,
+      extra_formats: settings.extra_formats,
+      resolution: settings.resolution,
+      output_fps: settings.output_fps,
+      letterbox: settings.letterbox,
+      pad_color: settings.pad_color,
+      rotation: Rotation::None,
+      caption: Caption::default(),
+      encoders,
+      encoder: settings.encoder,
+      crf: settings.crf,
+      preset: settings.preset,
+      presets: preset::load(),
+      preset_name: String::new(),
+      preset_error: None,
 
-      in_progress: Arc::new(Mutex::new(false)),
-      progress: Arc::new(Mutex::new(0.)),
-      ffmpeg_handle: None,
+      target_size: settings.target_size,
+      target_size_mb: settings.target_size_mb,
+      copy_metadata: settings.copy_metadata,
+      normalize_audio: settings.normalize_audio,
+      audio_codec: settings.audio_codec,
+      audio_kbps: settings.audio_kbps,
+      fast_seek: settings.fast_seek,
+      fade_in_ms: 0,
+      fade_out_ms: 0,
+
+      reveal_output: settings.reveal_output,
+      last_output_dir: settings.last_output_dir,
+      output_dir: settings.output_dir,
+      notify: settings.notify,
+
+      keybinds: Keybinds::new(settings.keybinds),
+      keybinds_open: false,
+      cheat_sheet_open: false,
+      light_theme: settings.light_theme,
+      mini: settings.mini,
+      preview_size: settings.preview_size,
+      preview_zoom: settings.preview_zoom,
+      toasts: Toasts::default(),
+      overwrite: None,
+      confirm_export: settings.confirm_export,
+      confirm: None,
+      trim_mismatch: None,
+      log: None,
+      log_view: None,
+
+      job: Job::new(),
+      quit_prompt: false,
+      quit_when_done: false,
+      allowed_to_close: false,
+      running: None,
+      was_in_progress: false,
+      title_progress: None,
+      taskbar: Taskbar::default(),
+      output: None,
+      frames_before: None,
+      queue: vec![],
+      processing_queue: false,
+      clips: vec![],
+      batch: Batch::default(),
+    };
+    if app.light_theme {
+      cc.egui_ctx.set_visuals(Visuals::light());
     }
+    let mut files = files.into_iter();
+    if let Some(path) = files.next() {
+      app.open(path);
+    }
+    app.up_next = files.collect();
+    app
   }
 }
 
 impl eframe::App for SnipApp {
-  fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+  fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    let in_progress = self.job.in_progress();
+    let finished = self.was_in_progress && !in_progress;
+    // Before job_finished, which may start a retry straight away
+    self.was_in_progress = in_progress;
+    if finished {
+      self.job_finished();
+    }
+
+    // winit has no taskbar progress, but the title shows up there on every platform
+    let title_progress = in_progress.then(|| {
+      let percent = (!self.job.is_indeterminate()).then(|| (self.job.progress().clamp(0., 1.) * 100.) as u32);
+      (percent, self.job.is_paused())
+    });
+    if title_progress != self.title_progress {
+      let title = match title_progress {
+        Some((percent, paused)) => format!(
+          "{}{} - snip",
+          percent.map(|percent| format!("{}%", percent)).unwrap_or_else(|| "working".to_owned()),
+          if paused { " (paused)" } else { "" },
+        ),
+        None => "snip".to_owned(),
+      };
+      frame.set_window_title(&title);
+      self.taskbar.set(frame, title_progress);
+      self.title_progress = title_progress;
+    }
+
     CentralPanel::default().show(ctx, |ui| {
-      ui.heading("Snip");
-      if self.player.is_none() {
-        match Player::new(
-          ctx,
-          &self.file_path.to_str().unwrap().to_string())
-            .and_then(|p| p.with_audio(&mut self.audio_device)
-        ) {
+      let mut open = None;
+      egui::menu::bar(ui, |ui| {
+        ui.menu_button("Recent files", |ui| {
+          self.recent_files.retain(|path| path.exists());
+          if self.recent_files.is_empty() {
+            ui.label("no recent files");
+          }
+          for path in &self.recent_files {
+            if ui.button(path.display().to_string()).clicked() {
+              open = Some(path.clone());
+              ui.close_menu();
+            }
+          }
+        });
+        if ui.button(if self.mini { "Full mode" } else { "Mini mode" }).clicked() {
+          self.mini = !self.mini;
+        }
+        if ui.button("Keybinds").clicked() {
+          self.keybinds_open = !self.keybinds_open;
+        }
+        if ui.button("?").on_hover_text("Keyboard shortcuts (? or F1)").clicked()
+          || text_typed(ui, "?")
+          || shortcut(ui, |i| i.key_pressed(Key::F1))
+          || (self.cheat_sheet_open && ui.input(|i| i.key_pressed(Key::Escape))) {
+            self.cheat_sheet_open = !self.cheat_sheet_open;
+        }
+        let theme = if self.light_theme { "🌙 Dark" } else { "☀ Light" };
+        if ui.button(theme).clicked() {
+          self.light_theme = !self.light_theme;
+          ctx.set_visuals(if self.light_theme { Visuals::light() } else { Visuals::dark() });
+        }
+      });
+      if !self.up_next.is_empty() {
+        ui.horizontal(|ui| {
+          let names: Vec<String> = self.up_next.iter().map(|path| path.display().to_string()).collect();
+          ui.label(format!("{} more file(s) to open", self.up_next.len())).on_hover_text(names.join("\n"));
+          if ui.button("Open next").clicked() {
+            open = Some(self.up_next.remove(0));
+          }
+          if ui.button("Dismiss").clicked() {
+            self.up_next.clear();
+          }
+        });
+      }
+      if let Some(path) = open {
+        self.open(path);
+      }
+      if !self.mini {
+        ui.heading("Snip");
+        ui.horizontal(|ui| {
+          ui.label("ffmpeg:");
+          let mut ffmpeg_path = self.ffmpeg_path.to_string_lossy().to_string();
+          let response = ui.text_edit_singleline(&mut ffmpeg_path);
+          if response.changed() {
+            self.ffmpeg_path = PathBuf::from(ffmpeg_path);
+          }
+          let mut recheck = response.lost_focus();
+          if ui.button("Browse").clicked() {
+            if let Some(path) = FileDialog::new()
+              .set_title("Locate ffmpeg")
+              .pick_file()
+            {
+              self.ffmpeg_path = path;
+              recheck = true;
+            }
+          }
+          if recheck {
+            self.ffmpeg_error = check_ffmpeg(&self.ffmpeg_path);
+            self.encoders = probe::available_encoders(&self.ffmpeg_path, &H264_ENCODERS);
+          }
+        });
+      }
+      if let Some(err) = &self.ffmpeg_error {
+        ui.colored_label(Color32::RED, err);
+      }
+      if !self.mini {
+        ui.horizontal(|ui| {
+          ui.label(format!("temp files: {}", self.scratch_dir().display()));
+          if ui.button("Browse").clicked() {
+            if let Some(dir) = FileDialog::new()
+              .set_title("Temp directory")
+              .pick_folder()
+            {
+              self.temp_dir = Some(dir);
+            }
+          }
+          if ui.add_enabled(self.temp_dir.is_some(), Button::new("Default")).clicked() {
+            self.temp_dir = None;
+          }
+        });
+      }
+      let mut dropped = ctx.input(|i| i.raw.dropped_files.clone())
+        .into_iter()
+        .filter_map(|file| file.path)
+        .filter(|path| is_video(path));
+      if let Some(path) = dropped.next() {
+        self.open(path);
+        if !self.job.in_progress() {
+          self.job.reset();
+        }
+      }
+      match &self.file_path {
+        None => {
+          ui.centered_and_justified(|ui| ui.heading("Drop a video here"));
+        },
+        Some(_) if ctx.input(|i| !i.raw.hovered_files.is_empty()) => {
+          let rect = ctx.screen_rect();
+          let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_overlay")));
+          painter.rect_filled(rect, 0., Color32::from_black_alpha(180));
+          painter.text(rect.center(), egui::Align2::CENTER_CENTER, "Drop a video here", egui::FontId::proportional(32.), Color32::WHITE);
+        },
+        _ => {},
+      }
+      // Player::new blocks until ffmpeg has the file open, which takes a while on
+      // big files, so the frame after opening just says so and it's made on the next
+      if let (Some(file_path), None, None, false) = (&self.file_path, &self.player, &self.player_error, self.player_loading) {
+        ui.horizontal(|ui| {
+          ui.spinner();
+          ui.label(format!("Loading {}...", file_path.file_name().unwrap_or_default().to_string_lossy()));
+        });
+        self.player_loading = true;
+        ctx.request_repaint();
+      } else if let (Some(file_path), None, None) = (&self.file_path, &self.player, &self.player_error) {
+        self.player_loading = false;
+        // The player only takes a String, so paths that aren't valid unicode can't be previewed
+        let player = match file_path.to_str() {
+          Some(path) => match &mut self.audio_device {
+            Ok(device) => Player::new(ctx, &path.to_owned()).and_then(|p| p.with_audio(device)),
+            Err(_) => Player::new(ctx, &path.to_owned()),
+          }.map_err(|err| err.to_string()),
+          None => Err("the preview can't open paths that aren't valid unicode".to_owned()),
+        };
+        match player {
           Ok(mut player) => {
             player.looping = false;
+            if let Some(elapsed) = self.positions.get(file_path).filter(|_| player.duration_ms > 0) {
+              player.seek((*elapsed as f32 / player.duration_ms as f32).clamp(0., 1.));
+            }
+            if self.fps <= 0. {
+              self.fps = player.framerate;
+            }
             self.player = Some(player);
           },
-          Err(_) => panic!("failed to create player"),
+          Err(err) => {
+            self.player_error = Some(format!("failed to open {}: {}", file_path.display(), err));
+          },
+        }
+      }
+      if let (Err(err), Some(_)) = (&self.audio_device, &self.file_path) {
+        ui.colored_label(Color32::YELLOW, format!("no audio output ({}), the preview is silent but exports keep their audio", err));
+      }
+      if let Some(err) = &self.player_error {
+        let mut choose = false;
+        egui::Frame::group(ui.style())
+          .fill(Color32::from_rgb(60, 16, 16))
+          .show(ui, |ui| {
+            ui.colored_label(Color32::RED, err);
+            choose = ui.button("Choose another file").clicked();
+          });
+        if choose {
+          if let Some(path) = FileDialog::new()
+            .set_title("Open video")
+            .pick_file()
+          {
+            self.open(path);
+          }
         }
       }
 
+      self.process_queue();
+
+      let mut cancel = false;
+      let mut snip = false;
+      let mut queue = false;
+      let mut add_clip = false;
+      let mut concat = false;
+      let mut batch = false;
+      let mut screenshot = false;
+      let mut export_frames = false;
+      let mut reexport = false;
+      let mut view_log = false;
+      let mut dismiss_mismatch = false;
+      let mut apply_preset = None;
+      let mut save_preset = false;
+      let mut delete_preset = false;
+      let mut pin_folder = false;
+      let command = self.command_preview();
+      let audio_encoder = self.audio_encoder();
+      let estimate = self.estimated_size_mb();
+      let scratch_dir = self.scratch_dir();
       if let Some(player) = self.player.as_mut() {
+        let in_progress = self.job.in_progress();
         // Player
-        ui.allocate_ui(player.size * 0.5, |ui| {
-          player.ui(ui, player.size * 0.5);
+        ui.horizontal(|ui| {
+          for size in PreviewSize::ALL {
+            ui.selectable_value(&mut self.preview_size, size, size.name());
+          }
+          if self.preview_size == PreviewSize::Zoom {
+            ui.add(Slider::new(&mut self.preview_zoom, 0.1..=4.).suffix("x"));
+          }
         });
+        let size = match self.preview_size {
+          PreviewSize::Fit => {
+            // Mini mode leaves room for just the timeline and a couple of rows of buttons,
+            // otherwise the controls get the bottom half
+            let reserved = if self.mini { MINI_CONTROLS_HEIGHT } else { ui.available_height() * 0.5 };
+            let room = ui.available_size() - egui::vec2(0., reserved);
+            player.size * (room.x / player.size.x).min(room.y / player.size.y).max(0.1)
+          },
+          PreviewSize::Actual => player.size,
+          PreviewSize::Zoom => player.size * self.preview_zoom,
+        };
+        let frame = ui.allocate_ui(size, |ui| {
+          player.ui(ui, size)
+        }).inner;
+        self.crop.ui(ui, frame.rect, player.size);
+        timeline::paint_on_scrubber(ui, frame.rect, player.duration_ms, self.start, self.end);
+        // Waveform
+        if !self.mini {
+          if let Some(ms) = self.waveform.ui(ui, player.elapsed_ms(), player.duration_ms) {
+            player.seek(ms as f32 / player.duration_ms as f32);
+          }
+        }
+        // Timeline
+        if let Some(ms) = self.timeline.ui(ui, player.duration_ms, player.elapsed_ms(), &mut self.start, &mut self.end, &self.segments, &self.markers, self.show_keyframes.then_some(&self.keyframes)) {
+          player.seek(ms as f32 / player.duration_ms as f32);
+        }
+        if !self.mini {
+          if let Some(file) = &self.file_path {
+            self.thumbnails.ui(ctx, &self.ffmpeg_path, file, player.size, self.timeline.hovered());
+          }
+          // Info
+          ui.collapsing("Info", |ui| {
+            let Some(info) = &self.info else {
+              ui.label("info unavailable");
+              return;
+            };
+            let unknown = || "unknown".to_owned();
+            ui.label(format!("Resolution: {}", info.resolution.map(|(w, h)| format!("{}x{}", w, h)).unwrap_or_else(unknown)));
+            ui.label(format!("Video codec: {}", info.video_codec.clone().unwrap_or_else(unknown)));
+            ui.label(format!("Audio codecs: {}", if info.audio_codecs.is_empty() { "none".to_owned() } else { info.audio_codecs.join(", ") }));
+            ui.label(format!("Container: {}", info.container.clone().unwrap_or_else(unknown)));
+            ui.label(format!("Framerate: {}", if self.fps > 0. { format!("{:.2} fps", self.fps) } else { unknown() }));
+            ui.label(format!("Bitrate: {}", info.bitrate.map(|b| format!("{} kb/s", b / 1000)).unwrap_or_else(unknown)));
+            ui.label(format!("Duration: {}", info.duration.map(|d| format_ms((d * 1000.) as i64)).unwrap_or_else(unknown)));
+          });
+        }
         // Controls
         ui.vertical_centered_justified(|ui| {
+          ui.monospace(format!("{} / {}", format_ms(player.elapsed_ms()), format_ms(player.duration_ms)));
           ui.horizontal(|ui| {
-            if ui.button("Start").clicked()
-              || ui.input(|i| i.key_pressed(Key::S)) {
-                self.start = Some(player.elapsed_ms());
+            if ui.add_enabled(!in_progress, Button::new("Start")).clicked()
+              || (!in_progress && shortcut(ui, |i| self.keybinds.pressed(i, Action::SetStart))) {
+                let elapsed = player.elapsed_ms();
+                self.start = Some(if self.snap_to_keyframe {
+                  self.keyframes.preceding(elapsed).unwrap_or(elapsed)
+                } else {
+                  elapsed
+                });
             }
-            if let Some(start) = self.start {
-              ui.label(format_ms(start));
-            } else {
-              ui.label("not set");
+            time_field(ui, &mut self.start_text, &mut self.start);
+            if ui.add_enabled(!in_progress, Button::new("Paste")).on_hover_text("Paste as start").clicked() {
+              paste_marker(&mut self.start, &mut self.toasts);
+            }
+            ms_field(ui, &mut self.start, 0, 0..=self.end.unwrap_or(player.duration_ms).max(0));
+            if self.snap_to_keyframe {
+              match self.keyframes.preceding(player.elapsed_ms()) {
+                Some(keyframe) => ui.label(format!("snaps to keyframe at {}", format_ms(keyframe))),
+                None if self.keyframes.failed() => ui.label("couldn't read keyframes, not snapping"),
+                None if !self.keyframes.is_loaded() => ui.label("loading keyframes..."),
+                None => ui.label("no keyframe before the playhead"),
+              };
             }
           });
           ui.horizontal(|ui| {
-            if ui.button("End").clicked()
-              || ui.input(|i| i.key_pressed(Key::E)) {
+            if ui.add_enabled(!in_progress, Button::new("End")).clicked()
+              || (!in_progress && shortcut(ui, |i| self.keybinds.pressed(i, Action::SetEnd))) {
                 self.end = Some(player.elapsed_ms());
             }
-            if let Some(end) = self.end {
-              ui.label(format_ms(end));
-            } else {
-              ui.label("not set");
+            time_field(ui, &mut self.end_text, &mut self.end);
+            if ui.add_enabled(!in_progress, Button::new("Paste")).on_hover_text("Paste as end").clicked() {
+              paste_marker(&mut self.end, &mut self.toasts);
+            }
+            let from = self.start.unwrap_or(0);
+            ms_field(ui, &mut self.end, player.duration_ms, from..=player.duration_ms.max(from));
+            // ffmpeg would just stop at the end of the file, leaving progress short of 100%
+            if let Some(end) = parse_ms(&self.end_text).filter(|end| *end > player.duration_ms) {
+              self.end_clamped = Some(end);
+            }
+            if self.end.is_none_or(|end| end < player.duration_ms) {
+              self.end_clamped = None;
+            }
+            if let Some(end) = self.end_clamped {
+              ui.colored_label(Color32::YELLOW, format!("{} is past the end of the file, clamped to {}", format_ms(end), format_ms(player.duration_ms)));
             }
           });
+          // Mini mode hides the buttons, not the shortcuts
+          let mut quick_clip = !in_progress && shortcut(ui, |i| self.keybinds.pressed(i, Action::QuickClip));
+          let mut select_all = !in_progress && shortcut(ui, |i| self.keybinds.pressed(i, Action::SelectAll));
+          let mut clear_selection = !in_progress && shortcut(ui, |i| self.keybinds.pressed(i, Action::ClearSelection));
+          if !self.mini {
+            ui.horizontal(|ui| {
+              quick_clip |= ui.add_enabled(!in_progress, Button::new("Clip around playhead")).clicked();
+              ui.label("±");
+              ui.add(DragValue::new(&mut self.quick_clip_ms).clamp_range(100..=600000).speed(100).suffix(" ms"));
+              select_all |= ui.add_enabled(!in_progress, Button::new("Select all")).clicked();
+              clear_selection |= ui.add_enabled(!in_progress, Button::new("Reset selection")).clicked();
+            });
+          }
+          if quick_clip {
+            let elapsed = player.elapsed_ms();
+            self.start = Some((elapsed - self.quick_clip_ms).max(0));
+            self.end = Some((elapsed + self.quick_clip_ms).min(player.duration_ms));
+          }
+          if select_all {
+            self.start = Some(0);
+            self.end = Some(player.duration_ms);
+          }
+          if clear_selection {
+            self.start = None;
+            self.end = None;
+          }
+          if player.duration_ms > 0 {
+            self.start = self.start.map(|start| start.min(player.duration_ms));
+            self.end = self.end.map(|end| end.min(player.duration_ms));
+          }
           match (self.start, self.end) {
             (Some(start), Some(end)) => {
               if start > end {
@@ -180,26 +2049,598 @@ impl eframe::App for SnipApp {
             },
             _ => {}
           }
+          if !self.mini {
+            ui.horizontal(|ui| {
+              let selection = match (self.start, self.end) {
+                (Some(start), Some(end)) if start < end => Some((start, end)),
+                _ => None,
+              };
+              if ui.add_enabled(selection.is_some(), Button::new("Add segment")).clicked() {
+                if let Some(segment) = selection {
+                  self.segments.push(segment);
+                  self.segments.sort();
+                  self.copy_streams = false;
+                }
+              }
+              if ui.add_enabled(!self.segments.is_empty(), Button::new("Clear segments")).clicked() {
+                self.segments.clear();
+              }
+            });
+            let mut remove = None;
+            for (i, (start, end)) in self.segments.iter().enumerate() {
+              ui.horizontal(|ui| {
+                ui.label(format!("Segment {}: {} - {}", i + 1, format_ms(*start), format_ms(*end)));
+                if ui.small_button("x").clicked() {
+                  remove = Some(i);
+                }
+              });
+            }
+            if let Some(i) = remove {
+              self.segments.remove(i);
+            }
+            if !self.segments.is_empty() {
+              ui.label("segments are exported joined together, start/end only mark the next one");
+            }
+            // Markers
+            ui.horizontal(|ui| {
+              ui.add(TextEdit::singleline(&mut self.marker_label).hint_text("label").desired_width(120.));
+              if ui.button("Add marker").clicked() {
+                let label = match self.marker_label.trim() {
+                  "" => format!("Marker {}", self.markers.len() + 1),
+                  label => label.to_owned(),
+                };
+                self.markers.push(Marker { ms: player.elapsed_ms(), label });
+                self.markers.sort_by_key(|marker| marker.ms);
+                self.marker_label.clear();
+              }
+            });
+            let next: Vec<Option<i64>> = self.markers.iter().skip(1).map(|marker| Some(marker.ms)).chain([None]).collect();
+            let mut remove = None;
+            let mut seek = None;
+            for (i, marker) in self.markers.iter_mut().enumerate() {
+              ui.horizontal(|ui| {
+                if ui.small_button(format_ms(marker.ms)).on_hover_text("seek here").clicked() {
+                  seek = Some(marker.ms);
+                }
+                ui.add(TextEdit::singleline(&mut marker.label).desired_width(120.));
+                if ui.small_button("Start").clicked() {
+                  self.start = Some(marker.ms);
+                }
+                if ui.small_button("End").clicked() {
+                  self.end = Some(marker.ms);
+                }
+                if let Some(next) = next[i] {
+                  if ui.small_button("To next").on_hover_text("select from here to the next marker").clicked() {
+                    self.start = Some(marker.ms);
+                    self.end = Some(next);
+                  }
+                }
+                if ui.small_button("x").clicked() {
+                  remove = Some(i);
+                }
+              });
+            }
+            if let Some(i) = remove {
+              self.markers.remove(i);
+            }
+            if let Some(ms) = seek.filter(|_| player.duration_ms > 0) {
+              player.seek((ms as f32 / player.duration_ms as f32).clamp(0., 1.));
+            }
+          }
         });
-        if ui.button("Cycle audio channel").clicked()
-          || ui.input(|i| i.key_pressed(Key::A)) {
-            player.cycle_audio_stream();
+        let mut cycle_audio = shortcut(ui, |i| self.keybinds.pressed(i, Action::CycleAudio));
+        let mut mute = shortcut(ui, |i| i.key_pressed(Key::M) && !i.modifiers.shift);
+        if !self.mini {
+          cycle_audio |= ui.button("Cycle audio channel").clicked();
+          if self.audio_streams.len() > 1 {
+            ui.label(format!("Audio stream: {}/{}", self.audio_stream + 1, self.audio_streams.len()));
+          }
+          ui.horizontal(|ui| {
+            let icon = if self.muted { "🔇" } else { "🔊" };
+            mute |= ui.button(icon).on_hover_text("Mute preview").clicked();
+            ui.add_enabled(!self.muted, Slider::new(&mut self.volume, 0. ..=1.)
+              .custom_formatter(|v, _| format!("{:.0}%", v * 100.))
+              .text("Volume"));
+          });
+        }
+        if cycle_audio {
+          player.cycle_audio_stream();
+          self.audio_stream = (self.audio_stream + 1) % self.audio_streams.len().max(1);
+        }
+        if mute {
+          self.muted = !self.muted;
+        }
+        // Flips between every stream and just the one being previewed
+        if shortcut(ui, |i| i.key_pressed(Key::M) && i.modifiers.shift) {
+          let all = self.audio_selected.iter().all(|s| *s);
+          for (i, selected) in self.audio_selected.iter_mut().enumerate() {
+            *selected = !all || i == self.audio_stream;
+          }
         }
         {
-          ui.checkbox(&mut self.merge_or_map, "Merge audio channels");
-          if ui.input(|i| i.key_pressed(Key::M)) {
-            self.merge_or_map = !self.merge_or_map;
+          let has_selection = matches!((self.start, self.end), (Some(start), Some(end)) if start <= end);
+          ui.add_enabled_ui(has_selection, |ui| {
+            ui.checkbox(&mut self.loop_selection, "Loop selection");
+          });
+          if has_selection && shortcut(ui, |i| i.modifiers.shift && i.key_pressed(Key::L)) {
+            self.loop_selection = !self.loop_selection;
+          }
+          match (self.start, self.end) {
+            (Some(start), Some(end)) if self.loop_selection && start <= end => {
+              let state = player.player_state.get();
+              if state == PlayerState::EndOfFile {
+                player.seek(start as f32 / player.duration_ms as f32);
+                player.start();
+              } else if state == PlayerState::Playing && player.elapsed_ms() >= end {
+                player.seek(start as f32 / player.duration_ms as f32);
+              }
+            },
+            _ => self.loop_selection = false,
+          }
+        }
+        if !self.mini {
+          ui.horizontal(|ui| {
+            ui.label("Seek step");
+            ui.add(DragValue::new(&mut self.seek_step_ms).clamp_range(1..=600000).speed(100).suffix(" ms"));
+            ui.label("fine (shift)");
+            ui.add(DragValue::new(&mut self.seek_step_fine_ms).clamp_range(1..=600000).speed(10).suffix(" ms"));
+          });
+          ComboBox::from_label("Speed")
+            .selected_text(format!("{}x", self.speed.speed))
+            .show_ui(ui, |ui| {
+              for speed in SPEEDS {
+                ui.selectable_value(&mut self.speed.speed, speed, format!("{}x", speed));
+              }
+            });
+        }
+        self.speed.drive(ctx, player);
+        // Emulated speeds seek constantly, which makes audio stutter
+        let muted = self.muted || !self.speed.is_native();
+        player.audio_volume.set(if muted { 0. } else { self.volume * player.max_audio_volume });
+        if !self.mini {
+          ui.add_enabled_ui(!self.copy_streams && self.format.has_audio(), |ui| {
+            ui.checkbox(&mut self.normalize_audio, "Normalize audio").on_hover_text("Evens out loudness with ffmpeg's loudnorm filter");
+            ui.horizontal(|ui| {
+              ComboBox::from_label("Audio codec")
+                .selected_text(self.audio_codec.name())
+                .show_ui(ui, |ui| {
+                  for codec in AudioCodec::ALL {
+                    ui.selectable_value(&mut self.audio_codec, codec, codec.name());
+                  }
+                });
+              let lossless = matches!(audio_encoder, Some("copy" | "pcm_s16le"));
+              ui.add_enabled(!lossless, Slider::new(&mut self.audio_kbps, 32..=320).suffix(" kb/s"));
+            });
+            if !self.audio_codec.fits(self.format) {
+              ui.colored_label(Color32::YELLOW, format!(
+                "{} can't go in {}, {} is used instead",
+                self.audio_codec.name(), self.format.name(), self.format.audio_codec().unwrap_or("no audio"),
+              ));
+            } else if self.audio_codec == AudioCodec::Copy {
+              ui.colored_label(Color32::YELLOW, if self.segments.is_empty() {
+                "copied audio skips fades and normalization, and the source codec has to fit the format"
+              } else {
+                "segments can't copy audio, so it's re-encoded"
+              });
+            }
+          });
+          ui.checkbox(&mut self.copy_metadata, "Copy metadata");
+          ui.checkbox(&mut self.fast_seek, "Fast seek").on_hover_text(
+            "Seek the input before decoding. Much faster near the end of long files, \
+            but the start can be slightly off on some formats. Off decodes everything \
+            up to the start, which is accurate but slow",
+          );
+          ui.horizontal(|ui| {
+            ComboBox::from_label("Export preset")
+              .selected_text("choose...")
+              .show_ui(ui, |ui| {
+                for preset in preset::builtins().into_iter().chain(self.presets.iter().cloned()) {
+                  if ui.selectable_label(false, &preset.name).clicked() {
+                    apply_preset = Some(preset);
+                  }
+                }
+              });
+            ui.add(TextEdit::singleline(&mut self.preset_name).hint_text("preset name").desired_width(120.));
+            let name = self.preset_name.trim();
+            let builtin = preset::builtins().iter().any(|p| p.name == name);
+            if ui.add_enabled(!name.is_empty() && !builtin, Button::new("Save current as preset")).clicked() {
+              save_preset = true;
+            }
+            let saved = self.presets.iter().any(|p| p.name == name);
+            if ui.add_enabled(saved, Button::new("Delete preset")).clicked() {
+              delete_preset = true;
+            }
+          });
+          if let Some(err) = &self.preset_error {
+            ui.colored_label(Color32::RED, format!("couldn't save presets: {}", err));
+          }
+          ComboBox::from_label("Format")
+            .selected_text(self.format.name())
+            .show_ui(ui, |ui| {
+              for format in OutputFormat::ALL {
+                ui.selectable_value(&mut self.format, format, format.name());
+              }
+            });
+          let selected = self.audio_selected.iter().filter(|selected| **selected).count();
+          ui.add_enabled_ui(self.segments.is_empty(), |ui| {
+            ui.horizontal(|ui| {
+              ui.label("Also export as").on_hover_text("made in the same pass with each format's default codecs");
+              for format in OutputFormat::ALL.into_iter().filter(|format| *format != self.format) {
+                let mut checked = self.extra_formats.contains(&format);
+                let merged = selected > 1 && format.has_audio();
+                let silent = selected == 0 && format.is_audio_only();
+                if ui.add_enabled(!merged && !silent, egui::Checkbox::new(&mut checked, format.name()))
+                  .on_disabled_hover_text(if merged { "only the main output can merge several audio streams" } else { "no audio stream selected" })
+                  .changed() {
+                  self.extra_formats.retain(|f| *f != format);
+                  if checked {
+                    self.extra_formats.push(format);
+                  }
+                }
+              }
+            });
+          });
+          ui.add_enabled_ui(!self.copy_streams, |ui| {
+            ui.horizontal(|ui| {
+              ui.label("Fade in");
+              ui.add(DragValue::new(&mut self.fade_in_ms).speed(10).suffix(" ms"));
+              ui.label("Fade out");
+              ui.add(DragValue::new(&mut self.fade_out_ms).speed(10).suffix(" ms"));
+            });
+          });
+          ui.add_enabled_ui(!self.copy_streams && !self.format.is_audio_only(), |ui| {
+            ui.horizontal(|ui| {
+              ui.label("Caption");
+              ui.text_edit_singleline(&mut self.caption.text);
+              ComboBox::from_id_source("caption_corner")
+                .selected_text(self.caption.corner.name())
+                .show_ui(ui, |ui| {
+                  for corner in Corner::ALL {
+                    ui.selectable_value(&mut self.caption.corner, corner, corner.name());
+                  }
+                });
+              ui.add_enabled(!self.caption.soft, DragValue::new(&mut self.caption.font_size)
+                .clamp_range(8..=200)
+                .suffix(" px"));
+              ui.add_enabled(self.format.subtitle_codec().is_some(), egui::Checkbox::new(&mut self.caption.soft, "As subtitle"));
+            });
+            ComboBox::from_label("Rotation")
+              .selected_text(self.rotation.name())
+              .show_ui(ui, |ui| {
+                for rotation in Rotation::ALL {
+                  ui.selectable_value(&mut self.rotation, rotation, rotation.name());
+                }
+              });
+            ComboBox::from_label("Resolution")
+              .selected_text(self.resolution.name())
+              .show_ui(ui, |ui| {
+                for resolution in Resolution::ALL {
+                  ui.selectable_value(&mut self.resolution, resolution, resolution.name());
+                }
+              });
+            ui.horizontal(|ui| {
+              ui.label("Framerate");
+              ui.add(DragValue::new(&mut self.output_fps)
+                .clamp_range(0. ..=240.)
+                .speed(0.5)
+                .custom_formatter(|fps, _| if fps > 0. { format!("{} fps", fps) } else { "source".to_owned() }));
+              if self.fps > 0. {
+                ui.label(format!("source is {:.2} fps", self.fps));
+              }
+            });
+            if self.fps > 0. && self.output_fps > self.fps {
+              ui.colored_label(Color32::YELLOW, "higher than the source, frames will just be duplicated");
+            }
+            ui.add_enabled_ui(self.resolution != Resolution::Original, |ui| {
+              ui.horizontal(|ui| {
+                ui.checkbox(&mut self.letterbox, "Letterbox to 16:9");
+                ui.add_enabled_ui(self.letterbox, |ui| {
+                  ui.color_edit_button_srgb(&mut self.pad_color);
+                });
+              });
+            });
+            ui.horizontal(|ui| {
+              ui.checkbox(&mut self.crop.editing, "Crop");
+              match self.crop.bounds() {
+                Some((w, h, x, y)) => ui.label(format!("{}x{} at {},{}", w, h, x, y)),
+                None => ui.label("drag over the preview to select a region"),
+              };
+              if ui.button("Reset crop").clicked() {
+                self.crop.reset();
+              }
+            });
+          });
+          ui.add_enabled_ui(!self.copy_streams && self.format.is_video(), |ui| {
+            ui.horizontal(|ui| {
+              ui.checkbox(&mut self.target_size, "Target file size");
+              ui.add_enabled(self.target_size, DragValue::new(&mut self.target_size_mb)
+                .clamp_range(0.1..=f64::MAX)
+                .speed(0.1)
+                .suffix(" MB"));
+            });
+          });
+          match estimate {
+            Some(mb) => ui.label(format!("Estimated size: ~{:.1} MB", mb)),
+            None => ui.label("Estimated size: unknown"),
+          };
+          ui.add_enabled_ui(!self.copy_streams && self.format.video_codec() == Some("libx264"), |ui| {
+            ComboBox::from_label("Encoder")
+              .selected_text(&self.encoder)
+              .show_ui(ui, |ui| {
+                let available = H264_ENCODERS.into_iter()
+                  .filter(|e| !is_hardware_encoder(e) || self.encoders.contains(e));
+                for encoder in available {
+                  ui.selectable_value(&mut self.encoder, encoder.to_owned(), encoder);
+                }
+              });
+          });
+          ui.add_enabled_ui(!self.copy_streams && self.format.video_codec() == Some("libx264") && self.encoder == "libx264", |ui| {
+            ui.add_enabled_ui(!self.target_size, |ui| {
+              ui.horizontal(|ui| {
+                ui.add(Slider::new(&mut self.crf, 0..=51).text("CRF"));
+                ui.label(crf_hint(self.crf));
+              });
+            });
+            ComboBox::from_label("Preset")
+              .selected_text(&self.preset)
+              .show_ui(ui, |ui| {
+                for preset in X264_PRESETS {
+                  ui.selectable_value(&mut self.preset, preset.to_owned(), preset);
+                }
+              });
+          });
+          ui.add_enabled_ui(self.format.is_video() && self.segments.is_empty(), |ui| {
+            ui.checkbox(&mut self.copy_streams, "Copy streams");
+            if self.copy_streams {
+              ui.checkbox(&mut self.copy_fallback, "Re-encode if copying fails");
+              ui.colored_label(Color32::YELLOW, "start may snap to the nearest preceding keyframe");
+            }
+          });
+          ui.horizontal(|ui| {
+            ui.checkbox(&mut self.snap_to_keyframe, "Snap start to keyframe");
+            ui.checkbox(&mut self.show_keyframes, "Show keyframes on the timeline");
+          });
+          if self.video_streams.len() > 1 {
+            ui.add_enabled_ui(!self.copy_streams && !self.format.is_audio_only(), |ui| {
+              ui.horizontal(|ui| {
+                ComboBox::from_label("Video stream")
+                  .selected_text(format!("{}: {}", self.video_stream + 1, self.video_streams[self.video_stream].label()))
+                  .show_ui(ui, |ui| {
+                    for (i, stream) in self.video_streams.iter().enumerate() {
+                      ui.selectable_value(&mut self.video_stream, i, format!("{}: {}", i + 1, stream.label()));
+                    }
+                  });
+                ui.label("the preview always shows the first one");
+              });
+            });
+          }
+          ui.add_enabled_ui(!self.copy_streams && self.format.has_audio(), |ui| {
+            // The list only ever holds probed streams, so amerge can't be asked for more than exist
+            if self.audio_streams.len() > 1 {
+              ui.label("Audio streams (more than one get merged)");
+            } else {
+              ui.label("Audio");
+            }
+            // Unticking the only stream would just make a silent export
+            let single = self.audio_streams.len() == 1;
+            for (i, stream) in self.audio_streams.iter().enumerate() {
+              ui.add_enabled(!single, egui::Checkbox::new(&mut self.audio_selected[i], format!("{}: {}", i + 1, stream.label())))
+                .on_disabled_hover_text("the only audio stream is always kept");
+            }
+            if self.audio_streams.is_empty() {
+              ui.label("couldn't probe audio streams, all of them will be kept");
+            } else if !self.audio_selected.contains(&true) {
+              ui.colored_label(Color32::YELLOW, "no audio will be kept");
+            }
+          });
+          if let Some(file) = self.file_path.as_ref().filter(|_| self.format.has_audio() && !self.audio_streams.is_empty()) {
+            ui.collapsing("Trim silence", |ui| {
+              ui.horizontal(|ui| {
+                ui.label("Quieter than");
+                ui.add(DragValue::new(&mut self.silence_db).clamp_range(-90. ..=0.).suffix(" dB"));
+                ui.label("for at least");
+                ui.add(DragValue::new(&mut self.silence_ms).clamp_range(50..=60000).speed(10).suffix(" ms"));
+              });
+              let detection = self.silence.detection();
+              if ui.add_enabled(detection != Some(Detection::Running), Button::new("Detect")).clicked() {
+                self.silence.detect(&self.ffmpeg_path, file, self.audio_stream, self.silence_db, self.silence_ms, player.duration_ms);
+              }
+              match detection {
+                Some(Detection::Running) => {
+                  ui.spinner();
+                  ctx.request_repaint_after(Duration::from_millis(100));
+                },
+                Some(Detection::Failed) => {
+                  ui.colored_label(Color32::RED, "silencedetect failed");
+                },
+                Some(Detection::Silent) => {
+                  ui.label("it's all silence at this threshold");
+                },
+                Some(Detection::Done(None, None)) => {
+                  ui.label("neither end is silent");
+                },
+                Some(Detection::Done(start, end)) => {
+                  ui.horizontal(|ui| {
+                    ui.label(format!("audible from {} to {}",
+                      start.map(format_ms).unwrap_or("start".to_owned()),
+                      end.map(format_ms).unwrap_or("end".to_owned())));
+                    if ui.button("Apply").clicked() {
+                      self.start = start;
+                      self.end = end;
+                    }
+                  });
+                },
+                None => {},
+              }
+            });
+          }
+          if let (Some(file), true) = (&self.file_path, self.start.is_some() || self.end.is_some()) {
+            ui.collapsing("First/last frame", |ui| {
+              // The end marker is exclusive, so the last frame is one before it
+              let frame_ms = if self.fps > 0. { (1000. / self.fps).round() as i64 } else { 1 };
+              let first = self.start.unwrap_or(0);
+              let last = (self.end.unwrap_or(player.duration_ms) - frame_ms).max(first);
+              self.boundaries.ui(ui, &self.ffmpeg_path, file, player.size, first, last);
+            });
+          }
+          ui.collapsing("Command preview", |ui| {
+            match command {
+              Some(command) => {
+                ui.add(TextEdit::multiline(&mut command.as_str())
+                  .code_editor()
+                  .desired_width(f32::INFINITY));
+                if ui.button("Copy command").clicked() {
+                  ui.output_mut(|o| o.copied_text = command);
+                  self.toasts.info("Copied command");
+                }
+              },
+              None => {
+                ui.label("nothing to preview");
+              },
+            }
+          });
+        }
+        // Snip
+        if ui.add_enabled(!in_progress, Button::new("Snip")).clicked()
+          || (!in_progress && self.confirm.is_none() && shortcut(ui, |i| self.keybinds.pressed(i, Action::Snip))) {
+            snip = true;
+        }
+        screenshot = !in_progress && shortcut(ui, |i| i.key_pressed(Key::P));
+        if !self.mini {
+          if ui.button("Add to queue").clicked() {
+            queue = true;
           }
-          if self.merge_or_map {
-            ui.add(Slider::new(&mut self.audio_merge, 1..=4));
+          if ui.button("Add to reel").clicked() {
+            add_clip = true;
           }
+          screenshot |= ui.add_enabled(!in_progress, Button::new("Screenshot")).clicked();
+          ui.horizontal(|ui| {
+            if ui.add_enabled(!in_progress, Button::new("Export frames")).clicked() {
+              export_frames = true;
+            }
+            ui.add(DragValue::new(&mut self.frames_fps)
+              .clamp_range(0. ..=240.)
+              .speed(0.5)
+              .custom_formatter(|fps, _| if fps > 0. { format!("{} fps", fps) } else { "every frame".to_owned() }));
+          });
+          if ui.add_enabled(!self.gif_preview.is_loading(), Button::new("Preview GIF")).clicked() {
+            if let Some(file) = &self.file_path {
+              let start = self.start.unwrap_or(0);
+              let end = self.end.unwrap_or(player.duration_ms);
+              self.gif_preview.generate(ctx, &self.ffmpeg_path, file, &scratch_dir, player.size, (start, end));
+            }
+          }
+          self.gif_preview.ui(ui);
+        }
+        if in_progress {
+          let progress = self.job.progress().clamp(0., 1.) as f32;
+          ui.horizontal(|ui| {
+            if self.job.is_indeterminate() {
+              ui.spinner();
+              ui.label("working…");
+            } else {
+              ui.add(ProgressBar::new(progress)
+                .desired_width(300.)
+                .text(format!("{:.2}%", progress * 100.)));
+            }
+            ui.label(format!("elapsed {}", format_duration(self.job.elapsed())));
+            match self.job.eta() {
+              Some(eta) => ui.label(format!("remaining {}", format_duration(eta))),
+              None => ui.label("remaining calculating…"),
+            };
+            match self.job.speed() {
+              Some(speed) => ui.label(format!("{:.1}x", speed)),
+              None => ui.label("—"),
+            };
+            let paused = self.job.is_paused();
+            if ui.button(if paused { "Resume" } else { "Pause" }).clicked() {
+              if let Err(err) = self.job.set_paused(!paused) {
+                self.toasts.error(format!("couldn't pause ffmpeg: {}", err));
+              }
+            }
+            if ui.button("Cancel").clicked() {
+              cancel = true;
+            }
+          });
+        } else if let Some(err) = self.job.error() {
+          egui::Frame::none()
+            .fill(Color32::from_rgb(60, 16, 16))
+            .inner_margin(6.)
+            .show(ui, |ui| {
+              ui.colored_label(Color32::RED, &err.summary);
+              if self.log.is_some() && ui.button("View log").clicked() {
+                view_log = true;
+              }
+              if !err.log.is_empty() {
+                ui.collapsing("ffmpeg output", |ui| {
+                  for line in &err.log {
+                    ui.monospace(line);
+                  }
+                });
+              }
+            });
+        } else if let Some(output) = self.output.as_ref().filter(|_| self.job.succeeded()) {
+          ui.horizontal(|ui| {
+            ui.label(format!("Saved {}", output.file_name().unwrap_or_default().to_string_lossy()));
+            if ui.button("Open folder").clicked() {
+              let _ = reveal(output);
+            }
+            if self.log.is_some() && ui.button("View log").clicked() {
+              view_log = true;
+            }
+          });
+          if let Some((job, actual)) = &self.trim_mismatch {
+            ui.horizontal(|ui| {
+              ui.colored_label(Color32::YELLOW, format!(
+                "came out {} long instead of {}, copies can only cut at keyframes",
+                format_ms(*actual as i64),
+                format_ms(job.duration as i64),
+              ));
+              if ui.button("Re-export with re-encode").clicked() {
+                reexport = true;
+              }
+              if ui.button("Dismiss").clicked() {
+                dismiss_mismatch = true;
+              }
+            });
+          }
+        }
+        if !self.mini {
+          ui.horizontal(|ui| {
+            ui.checkbox(&mut self.reveal_output, "Open folder when done");
+            ui.checkbox(&mut self.notify, "Notify when done");
+            ui.checkbox(&mut self.confirm_export, "Confirm before snipping");
+          });
+          ui.horizontal(|ui| {
+            ui.label("Output folder:");
+            match &self.output_dir {
+              Some(dir) => ui.label(dir.display().to_string()),
+              None => ui.label("last used"),
+            };
+            if ui.button("Pin folder").clicked() {
+              pin_folder = true;
+            }
+            if ui.add_enabled(self.output_dir.is_some(), Button::new("Unpin")).clicked() {
+              self.output_dir = None;
+            }
+          });
         }
-        if *self.in_progress.lock().unwrap() {
-          let progress = *self.progress.lock().unwrap();
-          ui.label(format!("Progress: {:.2}%", progress * 100.));
+        // Undo/redo
+        let settled = !ui.input(|i| i.pointer.any_down()) && !ctx.wants_keyboard_input();
+        self.history.track((self.start, self.end), settled);
+        if !ctx.wants_keyboard_input() {
+          let (undo, redo) = ui.input(|i| (
+            i.modifiers.command && !i.modifiers.shift && i.key_pressed(Key::Z),
+            i.modifiers.command && (i.key_pressed(Key::Y) || (i.modifiers.shift && i.key_pressed(Key::Z))),
+          ));
+          let markers = if undo { self.history.undo() } else if redo { self.history.redo() } else { None };
+          if let Some((start, end)) = markers {
+            self.start = start;
+            self.end = end;
+          }
         }
         // Keybinds
-        if ui.input(|i| i.key_pressed(Key::Space)) {
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::PlayPause)) {
           match player.player_state.get() {
             PlayerState::Playing => player.pause(),
             PlayerState::Paused => player.resume(),
@@ -211,37 +2652,500 @@ impl eframe::App for SnipApp {
             _ => {},
           }
         }
-        let step = if ui.input(|i| i.modifiers.shift) { 1000 } else { 5000 };
-        if ui.input(|i| i.key_pressed(Key::ArrowLeft)) {
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::Rewind)) {
+          self.speed.shuttle(player, false);
+        }
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::Pause)) {
+          self.speed.halt(player);
+        }
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::Forward)) {
+          self.speed.shuttle(player, true);
+        }
+        let step = if ui.input(|i| i.modifiers.shift) { self.seek_step_fine_ms } else { self.seek_step_ms };
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::SeekBack)) {
           let s = ((player.elapsed_ms() - step) as f32 / player.duration_ms as f32).max(0.);
           println!("{}", s);
           player.seek(((player.elapsed_ms() - step) as f32 / player.duration_ms as f32).max(0.));
         }
-        if ui.input(|i| i.key_pressed(Key::ArrowRight)) {
+        if shortcut(ui, |i| self.keybinds.pressed(i, Action::SeekForward)) {
           player.seek(((player.elapsed_ms() + step) as f32 / player.duration_ms as f32).min(1.));
         }
-        // Snip
-        if ui.input(|i| i.key_pressed(Key::Enter)) {
-          self.snip();
+        // Scrolling over the preview seeks one step per notch, and smooth
+        // scrolling builds up until it's worth a notch
+        if ui.rect_contains_pointer(frame.rect) {
+          let delta = ui.input(|i| if i.scroll_delta.y != 0. { i.scroll_delta.y } else { i.scroll_delta.x });
+          self.scroll += delta;
+          let notches = (self.scroll / SCROLL_NOTCH).trunc();
+          if notches != 0. && player.duration_ms > 0 {
+            self.scroll -= notches * SCROLL_NOTCH;
+            let step = if ui.input(|i| i.modifiers.shift) { self.seek_step_ms } else { self.seek_step_fine_ms };
+            let target = player.elapsed_ms() - notches as i64 * step;
+            player.seek((target as f32 / player.duration_ms as f32).clamp(0., 1.));
+          }
+        } else {
+          self.scroll = 0.;
+        }
+        let marker = if text_typed(ui, "[") {
+          self.start
+        } else if text_typed(ui, "]") {
+          self.end
+        } else {
+          None
+        };
+        if let Some(ms) = marker.filter(|_| player.duration_ms > 0) {
+          player.seek((ms as f32 / player.duration_ms as f32).clamp(0., 1.));
+        }
+        if self.fps > 0. {
+          let frame = 1000. / self.fps;
+          let duration = player.duration_ms as f64;
+          if text_typed(ui, ",") {
+            player.seek(((player.elapsed_ms() as f64 - frame).max(0.) / duration) as f32);
+          }
+          if text_typed(ui, ".") {
+            player.seek(((player.elapsed_ms() as f64 + frame).min(duration) / duration) as f32);
+          }
+        }
+      }
+      // Queue
+      if !self.queue.is_empty() {
+        ui.separator();
+        let mut remove = None;
+        for (i, job) in self.queue.iter().enumerate() {
+          ui.horizontal(|ui| {
+            match job.status {
+              JobStatus::Running if !self.job.is_indeterminate() => ui.label(format!("{:.0}%", self.job.progress() * 100.)),
+              status => ui.label(status.label()),
+            };
+            ui.label(job.output.file_name().unwrap_or_default().to_string_lossy());
+            ui.label(format!("{} - {}",
+              job.start.map(format_ms).unwrap_or("start".to_owned()),
+              job.end.map(format_ms).unwrap_or("end".to_owned())));
+            if ui.add_enabled(job.status != JobStatus::Running, Button::new("Remove")).clicked() {
+              remove = Some(i);
+            }
+          });
+        }
+        if let Some(i) = remove {
+          self.queue.remove(i);
+        }
+        ui.horizontal(|ui| {
+          let pending = self.queue.iter().any(|job| job.status == JobStatus::Pending);
+          if ui.add_enabled(!self.job.in_progress() && pending, Button::new("Process queue")).clicked() {
+            self.processing_queue = true;
+          }
+          if self.processing_queue {
+            let finished = self.queue.iter()
+              .filter(|job| matches!(job.status, JobStatus::Done | JobStatus::Failed))
+              .count();
+            let current = if self.job.is_indeterminate() { 0. } else { self.job.progress() };
+            let progress = (finished as f64 + current) / self.queue.len() as f64;
+            ui.add(ProgressBar::new(progress.clamp(0., 1.) as f32)
+              .desired_width(300.)
+              .text(format!("{} of {} done", finished, self.queue.len())));
+          }
+        });
+      }
+      // Reel
+      if !self.clips.is_empty() {
+        ui.separator();
+        let mut remove = None;
+        let mut swap = None;
+        let count = self.clips.len();
+        for (i, clip) in self.clips.iter().enumerate() {
+          ui.horizontal(|ui| {
+            ui.label(clip.path.file_name().unwrap_or_default().to_string_lossy());
+            ui.label(format!("{} - {}",
+              clip.start.map(format_ms).unwrap_or("start".to_owned()),
+              clip.end.map(format_ms).unwrap_or("end".to_owned())));
+            if ui.add_enabled(i > 0, Button::new("⏶")).clicked() {
+              swap = Some(i - 1);
+            }
+            if ui.add_enabled(i + 1 < count, Button::new("⏷")).clicked() {
+              swap = Some(i);
+            }
+            if ui.button("Remove").clicked() {
+              remove = Some(i);
+            }
+          });
+        }
+        if let Some(i) = swap {
+          self.clips.swap(i, i + 1);
+        }
+        if let Some(i) = remove {
+          self.clips.remove(i);
+        }
+        let can_concat = !self.job.in_progress() && count >= 2 && self.format.is_video();
+        if ui.add_enabled(can_concat, Button::new("Concatenate")).clicked() {
+          concat = true;
+        }
+      }
+      // Batch
+      if !self.mini {
+        ui.separator();
+        ui.collapsing("Batch trim", |ui| {
+          ui.horizontal(|ui| {
+            if ui.button("Add files").clicked() {
+              if let Some(files) = FileDialog::new().add_filter("Video", &VIDEO_EXTENSIONS).pick_files() {
+                self.batch.files.extend(files);
+              }
+            }
+            if ui.add_enabled(!self.batch.files.is_empty(), Button::new("Clear")).clicked() {
+              self.batch.files.clear();
+            }
+          });
+          let mut remove = None;
+          for (i, file) in self.batch.files.iter().enumerate() {
+            ui.horizontal(|ui| {
+              ui.label(file.file_name().unwrap_or_default().to_string_lossy());
+              if ui.button("Remove").clicked() {
+                remove = Some(i);
+              }
+            });
+          }
+          if let Some(i) = remove {
+            self.batch.files.remove(i);
+          }
+          ui.radio_value(&mut self.batch.trim_ends, false, "Same start/end as this video");
+          ui.horizontal(|ui| {
+            ui.radio_value(&mut self.batch.trim_ends, true, "Cut off the first");
+            ui.add_enabled(self.batch.trim_ends, DragValue::new(&mut self.batch.head_s).clamp_range(0. ..=86400.).suffix(" s"));
+            ui.label("and the last");
+            ui.add_enabled(self.batch.trim_ends, DragValue::new(&mut self.batch.tail_s).clamp_range(0. ..=86400.).suffix(" s"));
+          });
+          let can_batch = !self.batch.files.is_empty() && self.segments.is_empty();
+          if ui.add_enabled(can_batch, Button::new("Queue every file"))
+            .on_disabled_hover_text("segments only apply to this video")
+            .on_hover_text("uses the current export settings, outputs keep their file names")
+            .clicked() {
+              batch = true;
+          }
+        });
+      }
+      if self.job.in_progress() || self.processing_queue {
+        ctx.request_repaint_after(Duration::from_millis(250));
+      }
+
+      if snip {
+        self.snip();
+      }
+      if queue {
+        self.add_to_queue();
+      }
+      if add_clip {
+        self.add_clip();
+      }
+      if concat {
+        self.concat();
+      }
+      if batch {
+        self.queue_batch();
+      }
+      if screenshot {
+        self.screenshot();
+      }
+      if export_frames {
+        self.export_frames();
+      }
+      if let Some(log) = self.log.as_ref().filter(|_| view_log) {
+        self.log_view = Some(fs::read_to_string(log).unwrap_or_else(|err| format!("couldn't read `{}`: {}", log.display(), err)));
+      }
+      if dismiss_mismatch {
+        self.trim_mismatch = None;
+      }
+      if reexport {
+        if let Some((mut job, _)) = self.trim_mismatch.take() {
+          job.passes = job.reencode.take().unwrap();
+          job.fallback = None;
+          self.run_job(&job);
+        }
+      }
+      if cancel {
+        self.cancel();
+      }
+      if let Some(preset) = apply_preset {
+        self.apply_preset(&preset);
+      }
+      if save_preset {
+        self.save_preset();
+      }
+      if delete_preset {
+        self.delete_preset();
+      }
+      if pin_folder {
+        if let Some(dir) = self.output_dialog().pick_folder() {
+          self.output_dir = Some(dir);
         }
       }
     });
+
+    // Shown after the panel so the key that gets bound doesn't also trigger its action
+    egui::Window::new("Keybinds")
+      .open(&mut self.keybinds_open)
+      .resizable(false)
+      .show(ctx, |ui| self.keybinds.ui(ui));
+    self.toasts.ui(ctx);
+    if self.cheat_sheet_open {
+      self.keybinds.cheat_sheet(ctx);
+    }
+
+    let mut log_open = self.log_view.is_some();
+    if let Some(text) = &self.log_view {
+      egui::Window::new("Log")
+        .open(&mut log_open)
+        .default_size([600., 400.])
+        .show(ctx, |ui| {
+          if let Some(log) = &self.log {
+            ui.label(log.display().to_string());
+          }
+          egui::ScrollArea::both().show(ui, |ui| ui.monospace(text));
+        });
+    }
+    if !log_open {
+      self.log_view = None;
+    }
+
+    let mut choice = None;
+    if let Some((job, _)) = &self.overwrite {
+      egui::Window::new("File already exists")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(ctx, |ui| {
+          ui.label(format!("`{}` already exists", job.output.display()));
+          ui.horizontal(|ui| {
+            if ui.button("Overwrite").clicked() {
+              choice = Some(Overwrite::Replace);
+            }
+            if ui.button("Rename").clicked() {
+              choice = Some(Overwrite::Rename);
+            }
+            if ui.button("Cancel").clicked() {
+              choice = Some(Overwrite::Cancel);
+            }
+          });
+        });
+    }
+    if let Some(choice) = choice {
+      let (job, queue) = self.overwrite.take().unwrap();
+      match choice {
+        Overwrite::Replace if queue => self.queue.push(job),
+        Overwrite::Replace => {
+          self.run_job(&job);
+        },
+        Overwrite::Rename => {
+          let new = unused_path(&job.output);
+          if job.concat {
+            let job = self.build_concat(new);
+            self.submit(job, queue);
+          } else if let Some(job) = self.build_job(new) {
+            self.submit(job, queue);
+          }
+        },
+        Overwrite::Cancel => {},
+      }
+    }
+
+    let mut confirmed = None;
+    let estimate = self.estimated_size_mb();
+    if let Some((job, dont_ask)) = &mut self.confirm {
+      egui::Window::new("Snip this?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(ctx, |ui| {
+          egui::Grid::new("confirm").show(ui, |ui| {
+            ui.label("Source");
+            ui.label(job.source.display().to_string());
+            ui.end_row();
+            ui.label("Output");
+            ui.label(job.output.display().to_string());
+            ui.end_row();
+            if !job.extra_outputs.is_empty() {
+              ui.label("Also");
+              let names: Vec<String> = job.extra_outputs.iter().map(|path| path.display().to_string()).collect();
+              ui.label(names.join("\n"));
+              ui.end_row();
+            }
+            ui.label("Duration");
+            ui.label(format_ms(job.duration as i64));
+            ui.end_row();
+            ui.label("Format");
+            ui.label(self.format.name());
+            ui.end_row();
+            ui.label("Resolution");
+            ui.label(self.resolution.name());
+            ui.end_row();
+            ui.label("Estimated size");
+            ui.label(estimate.map(|mb| format!("~{:.1} MB", mb)).unwrap_or_else(|| "unknown".to_owned()));
+            ui.end_row();
+          });
+          ui.checkbox(dont_ask, "Don't ask again");
+          ui.horizontal(|ui| {
+            if ui.button("Snip").clicked() {
+              confirmed = Some(true);
+            }
+            if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+              confirmed = Some(false);
+            }
+          });
+        });
+    }
+    if let Some(confirmed) = confirmed {
+      let (job, dont_ask) = self.confirm.take().unwrap();
+      if confirmed {
+        if dont_ask {
+          self.confirm_export = false;
+        }
+        self.submit(job, false);
+      }
+    }
+
+    // A job that started and already ended this frame still needs a frame to notice
+    if self.was_in_progress && !self.job.in_progress() {
+      ctx.request_repaint();
+    }
+
+    let busy = self.job.in_progress() || self.processing_queue;
+    let mut quit = !busy && (self.quit_prompt || self.quit_when_done);
+    if self.quit_prompt && busy {
+      egui::Window::new("Quit?")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(ctx, |ui| {
+          ui.label("An export is still running, cancel it and quit?");
+          ui.horizontal(|ui| {
+            if ui.button("Cancel it and quit").clicked() {
+              quit = true;
+            }
+            if ui.button("Quit when it's done").clicked() {
+              self.quit_when_done = true;
+              self.quit_prompt = false;
+              self.toasts.info("snip will quit once the export is done");
+            }
+            if ui.button("Keep going").clicked() || ui.input(|i| i.key_pressed(Key::Escape)) {
+              self.quit_prompt = false;
+            }
+          });
+        });
+    }
+    if quit {
+      if busy {
+        self.cancel();
+      }
+      self.allowed_to_close = true;
+      frame.close();
+    }
+  }
+
+  // Closing mid-export would leave ffmpeg running with nobody to wait on it
+  fn on_close_event(&mut self) -> bool {
+    if self.allowed_to_close || !(self.job.in_progress() || self.processing_queue) {
+      return true;
+    }
+    self.quit_prompt = true;
+    false
+  }
+
+  // The window can also go away without asking, like on logout
+  fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+    if self.job.in_progress() {
+      self.cancel();
+    }
+    self.gif_preview.clear();
+    for cache in &self.waveform_caches {
+      let _ = fs::remove_file(cache);
+    }
+  }
+
+  fn save(&mut self, storage: &mut dyn eframe::Storage) {
+    eframe::set_value(storage, SETTINGS_KEY, &self.settings());
   }
 }
 
 fn main() {
   let options = eframe::NativeOptions {
     initial_window_size: Some(egui::Vec2 { x: 1280., y: 720. }),
+    persist_window: true,
     ..Default::default()
   };
-  let path = match args().into_iter().skip(1).next() {
-    Some(path) => PathBuf::from(path),
-    None => FileDialog::new()
+  let args: Vec<OsString> = args_os().skip(1).collect();
+  match Cli::parse(&args) {
+    Ok(Some(cli)) => std::process::exit(cli.run()),
+    Ok(None) => {},
+    Err(err) => {
+      eprintln!("{}\n{}", err, USAGE);
+      std::process::exit(2);
+    },
+  }
+  // File associations can pass flags along with the file, so only existing files count
+  let mut files: Vec<PathBuf> = args.iter()
+    .filter(|arg| !arg.to_str().is_some_and(|arg| arg.starts_with('-')))
+    .map(|arg| absolute(PathBuf::from(arg)))
+    .filter(|path| path.is_file())
+    .collect();
+  if files.is_empty() {
+    files.extend(FileDialog::new()
       .set_title("Open video")
-      .pick_file()
-      .expect("no video file provided"),
-  };
-  let _ = eframe::run_native("snip", options, Box::new(|_| {
-    Box::new(SnipApp::new(path))
+      .pick_file());
+  }
+  let _ = eframe::run_native("snip", options, Box::new(|cc| {
+    Box::new(SnipApp::new(cc, files))
   }));
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_typed_times() {
+    assert_eq!(parse_ms("00:01:30"), Some(90000));
+    assert_eq!(parse_ms("01:02:03.25"), Some(3723250));
+    assert_eq!(parse_ms(" 00:00:05.5 "), Some(5500));
+    for bad in ["00:90:00", "00:00:60", "00:00:05.1234", "00:00:.5", "1:30", "1.2.3", "00:0a:00", ""] {
+      assert_eq!(parse_ms(bad), None, "{:?}", bad);
+    }
+  }
+
+  #[test]
+  fn parses_pasted_timecodes() {
+    assert_eq!(parse_timecode("90.5"), Some(90500));
+    assert_eq!(parse_timecode("1:30"), Some(90000));
+    assert_eq!(parse_timecode("1:02:03.250"), Some(3723250));
+    assert_eq!(parse_timecode("  1:30\n"), Some(90000));
+    // Hours aren't capped, only the fields after them
+    assert_eq!(parse_timecode("100:00:00"), Some(360000000));
+    for bad in ["1:90", "1:00:60", ".5", "1.2.3", "1:2:3:4", "1::30", "-5", "1:30:", ""] {
+      assert_eq!(parse_timecode(bad), None, "{:?}", bad);
+    }
+  }
+
+  #[test]
+  fn leaves_plain_arguments_alone() {
+    assert_eq!(shell_quote("-c:v"), "-c:v");
+    assert_eq!(shell_quote("/tmp/out_1.mp4"), "/tmp/out_1.mp4");
+    assert_eq!(shell_quote(""), if cfg!(windows) { "\"\"" } else { "''" });
+  }
+
+  // What the preview shows has to come back out of a shell unchanged
+  #[cfg(unix)]
+  #[test]
+  fn quoted_arguments_survive_the_shell() {
+    for arg in ["my clip.mp4", "it's.mp4", "say \"hi\".mp4", "déjà vu – 日本.mkv", "$HOME `id` *.mp4", "'", ""] {
+      let output = Command::new("sh")
+        .args(["-c", &format!("printf %s {}", shell_quote(arg))])
+        .output()
+        .unwrap();
+      assert_eq!(String::from_utf8(output.stdout).unwrap(), arg);
+    }
+  }
+
+  #[test]
+  fn absolute_keeps_absolute_paths() {
+    let dir = std::env::current_dir().unwrap();
+    assert_eq!(absolute(PathBuf::from("clip.mp4")), dir.join("clip.mp4"));
+    assert_eq!(absolute(PathBuf::from("a:b.mp4")), dir.join("a:b.mp4"));
+    assert_eq!(absolute(dir.join("clip.mp4")), dir.join("clip.mp4"));
+  }
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+// A labelled moment in a file, kept between launches
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Marker {
+  pub ms: i64,
+  pub label: String,
+}
@@ -0,0 +1,88 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::format::{AudioCodec, OutputFormat, Resolution};
+
+// A named bundle of export settings
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset {
+  pub name: String,
+  pub format: OutputFormat,
+  pub resolution: Resolution,
+  pub letterbox: bool,
+  pub encoder: String,
+  pub crf: u8,
+  pub x264_preset: String,
+  pub target_size: bool,
+  pub target_size_mb: f64,
+  #[serde(default = "default_audio_codec")]
+  pub audio_codec: AudioCodec,
+  #[serde(default = "default_audio_kbps")]
+  pub audio_kbps: u64,
+}
+
+fn default_audio_codec() -> AudioCodec {
+  AudioCodec::Default
+}
+
+fn default_audio_kbps() -> u64 {
+  128
+}
+
+fn builtin(name: &str, format: OutputFormat, resolution: Resolution, crf: u8, x264_preset: &str, target_size_mb: Option<f64>, audio_kbps: u64) -> Preset {
+  Preset {
+    name: name.to_owned(),
+    format,
+    resolution,
+    letterbox: false,
+    encoder: "libx264".to_owned(),
+    crf,
+    x264_preset: x264_preset.to_owned(),
+    target_size: target_size_mb.is_some(),
+    target_size_mb: target_size_mb.unwrap_or(8.),
+    audio_codec: AudioCodec::Default,
+    audio_kbps,
+  }
+}
+
+pub fn builtins() -> Vec<Preset> {
+  vec![
+    builtin("Discord 8MB", OutputFormat::Mp4, Resolution::P720, 23, "medium", Some(8.), 96),
+    builtin("Twitter", OutputFormat::Mp4, Resolution::P720, 23, "medium", None, 128),
+    builtin("High quality", OutputFormat::Mp4, Resolution::Original, 18, "slow", None, 192),
+  ]
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+  let var = |name| std::env::var_os(name).map(PathBuf::from);
+  let base = if cfg!(windows) {
+    var("APPDATA")?
+  } else if cfg!(target_os = "macos") {
+    var("HOME")?.join("Library/Application Support")
+  } else {
+    var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))?
+  };
+  Some(base.join("snip"))
+}
+
+fn presets_path() -> Option<PathBuf> {
+  config_dir().map(|dir| dir.join("presets.json"))
+}
+
+// User presets, the built-in ones aren't written out
+pub fn load() -> Vec<Preset> {
+  presets_path()
+    .and_then(|path| fs::read_to_string(path).ok())
+    .and_then(|json| serde_json::from_str(&json).ok())
+    .unwrap_or_default()
+}
+
+pub fn save(presets: &[Preset]) -> Result<(), String> {
+  let path = presets_path().ok_or("couldn't find a config directory")?;
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+  }
+  let json = serde_json::to_string_pretty(presets).map_err(|err| err.to_string())?;
+  fs::write(&path, json).map_err(|err| format!("couldn't write `{}`: {}", path.display(), err))
+}
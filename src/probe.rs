@@ -0,0 +1,212 @@
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+// ffprobe is expected to live next to the configured ffmpeg binary
+pub fn ffprobe_path(ffmpeg: &Path) -> PathBuf {
+  let name = match ffmpeg.extension() {
+    Some(ext) => format!("ffprobe.{}", ext.to_string_lossy()),
+    None => "ffprobe".to_owned(),
+  };
+  match ffmpeg.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent.join(name),
+    _ => PathBuf::from(name),
+  }
+}
+
+// Which of `candidates` this ffmpeg build was compiled with. That doesn't
+// guarantee the hardware is there, so encodes can still fail
+pub fn available_encoders(ffmpeg: &Path, candidates: &[&'static str]) -> Vec<&'static str> {
+  let Ok(output) = Command::new(ffmpeg)
+    .args(["-hide_banner", "-encoders"])
+    .stdin(Stdio::null())
+    .stderr(Stdio::null())
+    .output() else {
+    return vec![];
+  };
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let listed: Vec<&str> = stdout.lines()
+    .filter_map(|line| line.split_whitespace().nth(1))
+    .collect();
+  candidates.iter().copied().filter(|c| listed.contains(c)).collect()
+}
+
+fn ffprobe(ffmpeg: &Path, args: &[&str], file: &Path) -> Option<String> {
+  let output = Command::new(ffprobe_path(ffmpeg))
+    .args(["-v", "error"])
+    .args(args)
+    .arg(file)
+    .stdin(Stdio::null())
+    .stderr(Stdio::null())
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn parse_rational(s: &str) -> Option<f64> {
+  match s.trim().split_once('/') {
+    Some((num, den)) => {
+      let num = num.parse::<f64>().ok()?;
+      let den = den.parse::<f64>().ok()?;
+      (den != 0.).then(|| num / den)
+    },
+    None => s.trim().parse().ok(),
+  }
+}
+
+// Keyframe timestamps in ms. Decodes every keyframe, so this can take a while
+pub fn probe_keyframes(ffmpeg: &Path, file: &Path) -> Option<Vec<i64>> {
+  let out = ffprobe(ffmpeg, &[
+    "-select_streams", "v:0",
+    "-skip_frame", "nokey",
+    "-show_frames",
+    "-show_entries", "frame=best_effort_timestamp_time",
+    "-of", "csv=p=0",
+  ], file)?;
+  let mut keyframes: Vec<i64> = out.lines()
+    .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+    .map(|secs| (secs * 1000.).round() as i64)
+    .collect();
+  keyframes.sort_unstable();
+  keyframes.dedup();
+  Some(keyframes)
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+  #[serde(default)]
+  streams: Vec<ProbeStream>,
+  format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+  codec_type: Option<String>,
+  codec_name: Option<String>,
+  avg_frame_rate: Option<String>,
+  width: Option<u32>,
+  height: Option<u32>,
+  #[serde(default)]
+  tags: HashMap<String, String>,
+}
+
+// ffprobe reports these numbers as strings
+#[derive(Deserialize)]
+struct ProbeFormat {
+  format_long_name: Option<String>,
+  format_name: Option<String>,
+  duration: Option<String>,
+  bit_rate: Option<String>,
+}
+
+#[derive(Default)]
+pub struct VideoInfo {
+  pub resolution: Option<(u32, u32)>,
+  pub video_codec: Option<String>,
+  pub audio_codecs: Vec<String>,
+  pub container: Option<String>,
+  pub bitrate: Option<u64>,
+  pub duration: Option<f64>,
+}
+
+pub struct VideoStream {
+  pub codec: Option<String>,
+  pub size: Option<(u32, u32)>,
+  pub title: Option<String>,
+}
+
+impl VideoStream {
+  pub fn label(&self) -> String {
+    let size = self.size.map(|(w, h)| format!("{}x{}", w, h));
+    let parts: Vec<&str> = [&size, &self.title, &self.codec]
+      .into_iter()
+      .flatten()
+      .map(|s| s.as_str())
+      .collect();
+    if parts.is_empty() { "unlabeled".to_owned() } else { parts.join(", ") }
+  }
+}
+
+// Recordings from the same capture setup share this, whatever they're called
+pub fn audio_layout(streams: &[AudioStream]) -> String {
+  streams.iter().map(|stream| stream.label()).collect::<Vec<_>>().join(" | ")
+}
+
+pub struct AudioStream {
+  pub codec: Option<String>,
+  pub language: Option<String>,
+  pub title: Option<String>,
+}
+
+impl AudioStream {
+  pub fn label(&self) -> String {
+    let parts: Vec<&str> = [&self.title, &self.language, &self.codec]
+      .into_iter()
+      .flatten()
+      .map(|s| s.as_str())
+      .collect();
+    if parts.is_empty() { "unlabeled".to_owned() } else { parts.join(", ") }
+  }
+}
+
+// Everything `open` needs from one ffprobe run
+#[derive(Default)]
+pub struct Probed {
+  pub fps: Option<f64>,
+  pub video_streams: Vec<VideoStream>,
+  pub audio_streams: Vec<AudioStream>,
+  pub info: VideoInfo,
+}
+
+pub fn probe(ffmpeg: &Path, file: &Path) -> Option<Probed> {
+  let out = ffprobe(ffmpeg, &[
+    "-print_format", "json",
+    "-show_format",
+    "-show_streams",
+  ], file)?;
+  let probed: ProbeOutput = serde_json::from_str(&out).ok()?;
+
+  let mut result = Probed::default();
+  for mut stream in probed.streams {
+    match stream.codec_type.as_deref() {
+      Some("video") => {
+        if result.video_streams.is_empty() {
+          result.fps = stream.avg_frame_rate.as_deref().and_then(parse_rational).filter(|fps| *fps > 0.);
+          result.info.resolution = stream.width.zip(stream.height);
+          result.info.video_codec = stream.codec_name.clone();
+        }
+        result.video_streams.push(VideoStream {
+          codec: stream.codec_name,
+          size: stream.width.zip(stream.height),
+          title: stream.tags.remove("title"),
+        });
+      },
+      Some("audio") => {
+        result.info.audio_codecs.extend(stream.codec_name.clone());
+        result.audio_streams.push(AudioStream {
+          codec: stream.codec_name,
+          language: stream.tags.remove("language"),
+          title: stream.tags.remove("title"),
+        });
+      },
+      _ => {},
+    }
+  }
+  if let Some(format) = probed.format {
+    result.info.container = format.format_long_name.or(format.format_name);
+    result.info.bitrate = format.bit_rate.and_then(|b| b.parse().ok());
+    result.info.duration = format.duration.and_then(|d| d.parse().ok());
+  }
+  Some(result)
+}
+
+pub fn probe_info(ffmpeg: &Path, file: &Path) -> Option<VideoInfo> {
+  probe(ffmpeg, file).map(|probed| probed.info)
+}
@@ -0,0 +1,54 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use crate::job::JobKind;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JobStatus {
+  Pending,
+  Running,
+  Done,
+  Failed,
+}
+
+impl JobStatus {
+  pub fn label(&self) -> &'static str {
+    match self {
+      JobStatus::Pending => "pending",
+      JobStatus::Running => "running",
+      JobStatus::Done => "done",
+      JobStatus::Failed => "failed",
+    }
+  }
+}
+
+// Everything needed to run a snip, captured when it's queued
+#[derive(Clone)]
+pub struct SnipJob {
+  pub source: PathBuf,
+  pub output: PathBuf,
+  // Made alongside `output` from the same decode
+  pub extra_outputs: Vec<PathBuf>,
+  pub start: Option<i64>,
+  pub end: Option<i64>,
+  pub passes: Vec<Vec<OsString>>,
+  pub kind: JobKind,
+  pub duration: f64,
+  pub cleanup: Vec<PathBuf>,
+  // Written out right before ffmpeg starts
+  pub temp_files: Vec<(PathBuf, String)>,
+  pub copy_metadata: bool,
+  pub status: JobStatus,
+  pub fallback: Option<Fallback>,
+  // Stream copies cut at keyframes, so these re-encode passes are offered if the length is off
+  pub reencode: Option<Vec<Vec<OsString>>>,
+  // Joins the clip list rather than cutting `source`
+  pub concat: bool,
+}
+
+// Passes to retry with if the job fails, like libx264 when a hardware
+// encoder does or a re-encode when a stream copy gets rejected
+#[derive(Clone)]
+pub struct Fallback {
+  pub passes: Vec<Vec<OsString>>,
+  pub note: String,
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use egui::Key;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  format::{AudioCodec, OutputFormat, Resolution},
+  keybinds::Action,
+  marker::Marker,
+  PreviewSize,
+};
+
+pub const SETTINGS_KEY: &str = "settings";
+
+// Persisted between launches, missing fields fall back to their defaults
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  pub ffmpeg_path: PathBuf,
+  // Two-pass logs, captions, previews and caches, None is the OS temp directory
+  pub temp_dir: Option<PathBuf>,
+  pub recent_files: Vec<PathBuf>,
+  // Where playback was when each file was last closed
+  pub positions: HashMap<PathBuf, i64>,
+  pub markers: HashMap<PathBuf, Vec<Marker>>,
+  // Which audio streams were kept, by stream layout, for files with more than one
+  pub audio_layouts: HashMap<String, Vec<bool>>,
+  pub volume: f32,
+  pub seek_step_ms: i64,
+  pub seek_step_fine_ms: i64,
+  pub quick_clip_ms: i64,
+  pub silence_db: f64,
+  pub silence_ms: i64,
+  // 0 keeps every frame
+  pub frames_fps: f64,
+  pub copy_streams: bool,
+  pub copy_fallback: bool,
+  pub format: OutputFormat,
+  pub extra_formats: Vec<OutputFormat>,
+  pub resolution: Resolution,
+  // 0 keeps the source framerate
+  pub output_fps: f64,
+  pub letterbox: bool,
+  pub pad_color: [u8; 3],
+  pub encoder: String,
+  pub crf: u8,
+  pub preset: String,
+  pub target_size: bool,
+  pub target_size_mb: f64,
+  pub copy_metadata: bool,
+  pub normalize_audio: bool,
+  pub audio_codec: AudioCodec,
+  pub audio_kbps: u64,
+  pub fast_seek: bool,
+  pub snap_to_keyframe: bool,
+  pub show_keyframes: bool,
+  pub reveal_output: bool,
+  pub last_output_dir: Option<PathBuf>,
+  // Pinned by the user, overrides the last used directory
+  pub output_dir: Option<PathBuf>,
+  pub notify: bool,
+  pub confirm_export: bool,
+  pub keybinds: HashMap<Action, Key>,
+  pub light_theme: bool,
+  pub mini: bool,
+  pub preview_size: PreviewSize,
+  pub preview_zoom: f32,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      ffmpeg_path: PathBuf::from("ffmpeg"),
+      temp_dir: None,
+      recent_files: vec![],
+      positions: HashMap::new(),
+      markers: HashMap::new(),
+      audio_layouts: HashMap::new(),
+      volume: 0.5,
+      seek_step_ms: 5000,
+      seek_step_fine_ms: 1000,
+      quick_clip_ms: 5000,
+      silence_db: -40.,
+      silence_ms: 500,
+      frames_fps: 0.,
+      copy_streams: false,
+      copy_fallback: true,
+      format: OutputFormat::Mp4,
+      extra_formats: vec![],
+      resolution: Resolution::Original,
+      output_fps: 0.,
+      letterbox: false,
+      pad_color: [0, 0, 0],
+      encoder: "libx264".to_owned(),
+      crf: 23,
+      preset: "medium".to_owned(),
+      target_size: false,
+      target_size_mb: 8.,
+      copy_metadata: true,
+      normalize_audio: false,
+      audio_codec: AudioCodec::Default,
+      audio_kbps: 128,
+      fast_seek: true,
+      snap_to_keyframe: false,
+      show_keyframes: true,
+      reveal_output: false,
+      last_output_dir: None,
+      output_dir: None,
+      notify: true,
+      confirm_export: true,
+      keybinds: HashMap::new(),
+      light_theme: false,
+      mini: false,
+      preview_size: PreviewSize::Fit,
+      preview_zoom: 0.5,
+    }
+  }
+}
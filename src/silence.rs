@@ -0,0 +1,94 @@
+use std::{
+  path::Path,
+  process::{Command, Stdio},
+  sync::{Arc, Mutex},
+};
+
+// A silence this close to either end of the file counts as touching it
+const EDGE_MS: i64 = 50;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Detection {
+  Running,
+  Failed,
+  // Nothing but silence
+  Silent,
+  // Where the audible part starts and ends, None when that end isn't silent
+  Done(Option<i64>, Option<i64>),
+}
+
+// Leading and trailing silence as found by ffmpeg's silencedetect
+#[derive(Default)]
+pub struct Silence {
+  detection: Arc<Mutex<Option<Detection>>>,
+}
+
+// Every (start, end) silencedetect printed, in ms. A silence that runs to the
+// end of the file may not get an end on older ffmpeg versions
+fn parse_silences(stderr: &str) -> Vec<(i64, Option<i64>)> {
+  let value = |line: &str, key: &str| -> Option<i64> {
+    let rest = &line[line.find(key)? + key.len()..];
+    let secs: f64 = rest.split_whitespace().next()?.parse().ok()?;
+    Some((secs * 1000.) as i64)
+  };
+  let mut silences: Vec<(i64, Option<i64>)> = vec![];
+  for line in stderr.lines() {
+    if let Some(start) = value(line, "silence_start: ") {
+      silences.push((start, None));
+    } else if let Some(end) = value(line, "silence_end: ") {
+      if let Some(last) = silences.last_mut().filter(|(_, end)| end.is_none()) {
+        last.1 = Some(end);
+      }
+    }
+  }
+  silences
+}
+
+fn detect(ffmpeg: &Path, file: &Path, stream: usize, noise_db: f64, min_ms: i64, duration_ms: i64) -> Option<Detection> {
+  let filter = format!("silencedetect=noise={}dB:d={}", noise_db, min_ms as f64 / 1000.);
+  let output = Command::new(ffmpeg)
+    .args(["-hide_banner", "-nostats", "-i"])
+    .arg(file)
+    .args(["-map", &format!("0:a:{}", stream), "-af", &filter, "-f", "null", "-"])
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  let silences = parse_silences(&String::from_utf8_lossy(&output.stderr));
+  let start = silences.first()
+    .filter(|(start, _)| *start <= EDGE_MS)
+    .map(|(_, end)| end.unwrap_or(duration_ms));
+  if start.is_some_and(|start| start >= duration_ms - EDGE_MS) {
+    return Some(Detection::Silent);
+  }
+  let end = silences.last()
+    .filter(|(_, end)| end.unwrap_or(duration_ms) >= duration_ms - EDGE_MS)
+    .map(|(start, _)| *start)
+    .filter(|end| *end > start.unwrap_or(0));
+  Some(Detection::Done(start, end))
+}
+
+impl Silence {
+  pub fn detection(&self) -> Option<Detection> {
+    *self.detection.lock().unwrap()
+  }
+
+  pub fn clear(&mut self) {
+    self.detection = Arc::default();
+  }
+
+  // Runs on its own thread, a fresh slot means a stale run can't land after `clear`
+  pub fn detect(&mut self, ffmpeg: &Path, file: &Path, stream: usize, noise_db: f64, min_ms: i64, duration_ms: i64) {
+    let detection = Arc::new(Mutex::new(Some(Detection::Running)));
+    self.detection = detection.clone();
+    let ffmpeg = ffmpeg.to_path_buf();
+    let file = file.to_path_buf();
+    std::thread::spawn(move || {
+      let found = detect(&ffmpeg, &file, stream, noise_db, min_ms, duration_ms).unwrap_or(Detection::Failed);
+      *detection.lock().unwrap() = Some(found);
+    });
+  }
+}
@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use egui::Context;
+use egui_video::{Player, PlayerState};
+
+pub const SPEEDS: [f32; 6] = [0.25, 0.5, 1., 1.5, 2., 4.];
+
+// egui-video can only play at 1x, so other speeds are emulated by
+// periodically seeking to where the playhead should be
+const SEEK_INTERVAL: Duration = Duration::from_millis(250);
+// Further than this from the expected position means the user seeked elsewhere
+const RESYNC_MS: f64 = 2000.;
+
+pub struct PlaybackSpeed {
+  pub speed: f32,
+  clock: Option<(Instant, i64)>,
+  last_seek: Option<Instant>,
+}
+
+impl Default for PlaybackSpeed {
+  fn default() -> Self {
+    Self {
+      speed: 1.,
+      clock: None,
+      last_seek: None,
+    }
+  }
+}
+
+impl PlaybackSpeed {
+  pub fn is_native(&self) -> bool {
+    self.speed == 1.
+  }
+
+  // J/L: playing in the same direction again steps up through SPEEDS,
+  // anything else starts over at 1x. Reverse is emulated like slow speeds
+  pub fn shuttle(&mut self, player: &mut Player, forward: bool) {
+    let sign = if forward { 1. } else { -1. };
+    let current = self.speed * sign;
+    let playing = player.player_state.get() == PlayerState::Playing;
+    self.speed = if playing && current >= 1. {
+      sign * SPEEDS.into_iter().find(|speed| *speed > current).unwrap_or(current)
+    } else {
+      sign
+    };
+    self.clock = None;
+    match player.player_state.get() {
+      PlayerState::Paused => player.resume(),
+      PlayerState::EndOfFile if forward => {
+        player.seek(0.);
+        player.start();
+      },
+      PlayerState::Stopped | PlayerState::EndOfFile => player.start(),
+      _ => {},
+    }
+  }
+
+  // K
+  pub fn halt(&mut self, player: &mut Player) {
+    if player.player_state.get() == PlayerState::Playing {
+      player.pause();
+    }
+    self.speed = 1.;
+    self.clock = None;
+  }
+
+  pub fn drive(&mut self, ctx: &Context, player: &mut Player) {
+    match player.player_state.get() {
+      PlayerState::Playing if !self.is_native() => {},
+      PlayerState::Seeking(_) if self.clock.is_some() => return,
+      _ => {
+        self.clock = None;
+        return;
+      },
+    }
+    let now = Instant::now();
+    let (since, from) = *self.clock.get_or_insert((now, player.elapsed_ms()));
+    let target = from as f64 + (now - since).as_millis() as f64 * self.speed as f64;
+    if (player.elapsed_ms() as f64 - target).abs() > RESYNC_MS {
+      self.clock = Some((now, player.elapsed_ms()));
+      return;
+    }
+    if target <= 0. && self.speed < 0. {
+      player.seek(0.);
+      self.halt(player);
+      return;
+    }
+    if self.last_seek.is_none_or(|last| now - last >= SEEK_INTERVAL) {
+      player.seek((target / player.duration_ms as f64).clamp(0., 1.) as f32);
+      self.last_seek = Some(now);
+    }
+    ctx.request_repaint_after(SEEK_INTERVAL);
+  }
+}
@@ -0,0 +1,117 @@
+// Export progress on the taskbar button on Windows and on the launcher icon on
+// Linux (the Unity LauncherEntry API, which KDE and most docks read too).
+// Elsewhere the window title is all there is
+
+// What to show: percent done, None while it's unknown, and whether it's paused
+pub type Progress = Option<(Option<u32>, bool)>;
+
+#[cfg(windows)]
+mod imp {
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use windows::Win32::{
+    Foundation::HWND,
+    System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED},
+    UI::Shell::{ITaskbarList3, TaskbarList, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED},
+  };
+
+  use super::Progress;
+
+  pub struct Taskbar {
+    list: ITaskbarList3,
+    hwnd: HWND,
+  }
+
+  impl Taskbar {
+    pub fn new(frame: &eframe::Frame) -> Option<Self> {
+      let RawWindowHandle::Win32(handle) = frame.raw_window_handle() else {
+        return None;
+      };
+      unsafe {
+        // Fails harmlessly if winit already set the thread up
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let list: ITaskbarList3 = CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER).ok()?;
+        list.HrInit().ok()?;
+        Some(Self { list, hwnd: HWND(handle.hwnd as isize) })
+      }
+    }
+
+    pub fn set(&self, progress: Progress) {
+      unsafe {
+        let _ = match progress {
+          None => self.list.SetProgressState(self.hwnd, TBPF_NOPROGRESS),
+          Some((None, _)) => self.list.SetProgressState(self.hwnd, TBPF_INDETERMINATE),
+          Some((Some(percent), paused)) => self.list.SetProgressState(self.hwnd, if paused { TBPF_PAUSED } else { TBPF_NORMAL })
+            .and_then(|_| self.list.SetProgressValue(self.hwnd, percent as u64, 100)),
+        };
+      }
+    }
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+  use std::collections::HashMap;
+
+  use zbus::{blocking::Connection, zvariant::Value};
+
+  use super::Progress;
+
+  // Docks match this against the installed .desktop file
+  const APP_URI: &str = "application://snip.desktop";
+
+  pub struct Taskbar {
+    connection: Connection,
+  }
+
+  impl Taskbar {
+    pub fn new(_frame: &eframe::Frame) -> Option<Self> {
+      Some(Self { connection: Connection::session().ok()? })
+    }
+
+    // There's no indeterminate or paused state, so those just show as far as it got
+    pub fn set(&self, progress: Progress) {
+      let mut properties: HashMap<&str, Value> = HashMap::new();
+      properties.insert("progress-visible", progress.is_some().into());
+      if let Some((Some(percent), _)) = progress {
+        properties.insert("progress", (percent as f64 / 100.).into());
+      }
+      let _ = self.connection.emit_signal(
+        None::<&str>,
+        "/com/github/uzervlad/snip",
+        "com.canonical.Unity.LauncherEntry",
+        "Update",
+        &(APP_URI, properties),
+      );
+    }
+  }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod imp {
+  use super::Progress;
+
+  pub struct Taskbar;
+
+  impl Taskbar {
+    pub fn new(_frame: &eframe::Frame) -> Option<Self> {
+      Some(Self)
+    }
+
+    pub fn set(&self, _progress: Progress) {}
+  }
+}
+
+// Made on the first frame, since Windows needs the window handle. None if the
+// platform's API isn't there, like without a session bus
+#[derive(Default)]
+pub struct Taskbar {
+  inner: Option<Option<imp::Taskbar>>,
+}
+
+impl Taskbar {
+  pub fn set(&mut self, frame: &eframe::Frame, progress: Progress) {
+    if let Some(taskbar) = self.inner.get_or_insert_with(|| imp::Taskbar::new(frame)) {
+      taskbar.set(progress);
+    }
+  }
+}
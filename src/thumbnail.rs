@@ -0,0 +1,161 @@
+use std::{
+  collections::VecDeque,
+  path::Path,
+  process::{Command, Stdio},
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use egui::{ColorImage, Context, Id, TextureHandle, TextureOptions, Ui, Vec2};
+
+const WIDTH: usize = 160;
+const CACHE_SIZE: usize = 32;
+// Hovering anywhere within this range shows the same thumbnail
+const BUCKET_MS: i64 = 1000;
+// The cursor has to rest on a bucket this long before ffmpeg is run
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+// A bucket and its frame, None if ffmpeg couldn't grab one
+type Grabbed = (i64, Option<ColorImage>);
+// Which markers these are the first and last frames of
+type GrabbedBoundaries = ((i64, i64), [Option<ColorImage>; 2]);
+
+#[derive(Default)]
+pub struct Thumbnails {
+  // Oldest first, failed grabs are kept as None so they aren't retried
+  cache: VecDeque<(i64, Option<TextureHandle>)>,
+  loading: bool,
+  loaded: Arc<Mutex<Option<Grabbed>>>,
+  hovering: Option<(i64, Instant)>,
+}
+
+// Frames are piped out as raw RGBA so there's nothing to decode
+fn grab(ffmpeg: &Path, file: &Path, ms: i64, [w, h]: [usize; 2]) -> Option<ColorImage> {
+  let output = Command::new(ffmpeg)
+    .args(["-v", "error", "-ss", &format!("{}", ms as f64 / 1000.), "-i"])
+    .arg(file)
+    .args(["-frames:v", "1", "-vf", &format!("scale={}:{}", w, h)])
+    .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+    .stdin(Stdio::null())
+    .stderr(Stdio::null())
+    .output()
+    .ok()?;
+  (output.status.success() && output.stdout.len() == w * h * 4)
+    .then(|| ColorImage::from_rgba_unmultiplied([w, h], &output.stdout))
+}
+
+impl Thumbnails {
+  pub fn ui(&mut self, ctx: &Context, ffmpeg: &Path, file: &Path, source: Vec2, hovered: Option<i64>) {
+    if let Some((bucket, image)) = self.loaded.lock().unwrap().take() {
+      self.loading = false;
+      if self.cache.len() >= CACHE_SIZE {
+        self.cache.pop_front();
+      }
+      let texture = image.map(|image| ctx.load_texture(format!("thumbnail-{}", bucket), image, TextureOptions::LINEAR));
+      self.cache.push_back((bucket, texture));
+    }
+
+    let Some(ms) = hovered else {
+      self.hovering = None;
+      return;
+    };
+    let bucket = ms / BUCKET_MS * BUCKET_MS;
+    if let Some((_, texture)) = self.cache.iter().find(|(b, _)| *b == bucket) {
+      if let Some(texture) = texture {
+        egui::show_tooltip_at_pointer(ctx, Id::new("thumbnail"), |ui| {
+          ui.image(texture);
+        });
+      }
+      return;
+    }
+
+    let now = Instant::now();
+    let since = match self.hovering {
+      Some((b, since)) if b == bucket => since,
+      _ => {
+        self.hovering = Some((bucket, now));
+        now
+      },
+    };
+    if !self.loading && now - since >= DEBOUNCE && source.x > 0. {
+      self.loading = true;
+      let height = ((WIDTH as f32 * source.y / source.x) as usize).max(2) & !1;
+      let ffmpeg = ffmpeg.to_path_buf();
+      let file = file.to_path_buf();
+      let loaded = self.loaded.clone();
+      let ctx = ctx.clone();
+      std::thread::spawn(move || {
+        let image = grab(&ffmpeg, &file, bucket, [WIDTH, height]);
+        *loaded.lock().unwrap() = Some((bucket, image));
+        ctx.request_repaint();
+      });
+    }
+    ctx.request_repaint_after(DEBOUNCE);
+  }
+}
+
+// The first and last frame of the selection, regrabbed once the markers settle
+#[derive(Default)]
+pub struct Boundaries {
+  wanted: Option<((i64, i64), Instant)>,
+  shown: Option<(i64, i64)>,
+  loading: bool,
+  loaded: Arc<Mutex<Option<GrabbedBoundaries>>>,
+  textures: [Option<TextureHandle>; 2],
+}
+
+impl Boundaries {
+  pub fn ui(&mut self, ui: &mut Ui, ffmpeg: &Path, file: &Path, source: Vec2, first: i64, last: i64) {
+    let ctx = ui.ctx().clone();
+    if let Some((markers, images)) = self.loaded.lock().unwrap().take() {
+      self.loading = false;
+      self.shown = Some(markers);
+      self.textures = images.map(|image| image.map(|image| ctx.load_texture("boundary", image, TextureOptions::LINEAR)));
+    }
+
+    let markers = (first, last);
+    if self.shown != Some(markers) {
+      let now = Instant::now();
+      let since = match self.wanted {
+        Some((wanted, since)) if wanted == markers => since,
+        _ => {
+          self.wanted = Some((markers, now));
+          now
+        },
+      };
+      if !self.loading && now - since >= DEBOUNCE && source.x > 0. {
+        self.loading = true;
+        let height = ((WIDTH as f32 * source.y / source.x) as usize).max(2) & !1;
+        let ffmpeg = ffmpeg.to_path_buf();
+        let file = file.to_path_buf();
+        let loaded = self.loaded.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+          let images = [first, last].map(|ms| grab(&ffmpeg, &file, ms, [WIDTH, height]));
+          *loaded.lock().unwrap() = Some((markers, images));
+          ctx.request_repaint();
+        });
+      }
+      ctx.request_repaint_after(DEBOUNCE);
+    }
+
+    ui.horizontal(|ui| {
+      for (texture, name) in self.textures.iter().zip(["First frame", "Last frame"]) {
+        ui.vertical(|ui| {
+          ui.label(name);
+          match texture {
+            Some(texture) => {
+              ui.image(texture);
+            },
+            None if self.shown.is_none() => {
+              ui.label("loading...");
+            },
+            None => {
+              ui.label("no frame");
+            },
+          }
+        });
+      }
+    });
+  }
+}
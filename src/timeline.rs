@@ -0,0 +1,241 @@
+use egui::{Align2, Color32, FontId, Pos2, Rect, Rounding, Sense, Stroke, Ui, Vec2};
+
+use crate::{keyframes::Keyframes, marker::Marker};
+
+const HANDLE_GRAB: f32 = 8.;
+const MAX_ZOOM: f32 = 256.;
+const ZOOM_STEP: f32 = 2.;
+// Where egui-video draws its seekbar: inset 10px each side, 20px up from the bottom, 3px tall
+// and growing another 3px upwards on hover
+const SCRUBBER_INSET: f32 = 10.;
+const SCRUBBER_OFFSET: f32 = 20.;
+const SCRUBBER_HEIGHT: f32 = 6.;
+const SELECTION: Color32 = Color32::from_rgb(80, 160, 255);
+const MARKER: Color32 = Color32::from_rgb(255, 160, 40);
+
+// Marks the selection over the player's own seekbar, `frame` is the rect the player drew in
+pub fn paint_on_scrubber(ui: &Ui, frame: Rect, duration_ms: i64, start: Option<i64>, end: Option<i64>) {
+  if duration_ms <= 0 || (start.is_none() && end.is_none()) {
+    return;
+  }
+  let left = frame.left() + SCRUBBER_INSET;
+  let width = frame.width() - SCRUBBER_INSET * 2.;
+  let to_x = |ms: i64| left + width * (ms as f32 / duration_ms as f32).clamp(0., 1.);
+  let bottom = frame.bottom() - SCRUBBER_OFFSET + 3.;
+  let y_range = bottom - SCRUBBER_HEIGHT..=bottom;
+  let painter = ui.painter_at(frame);
+  let region = Rect::from_x_y_ranges(to_x(start.unwrap_or(0))..=to_x(end.unwrap_or(duration_ms)), y_range.clone());
+  painter.rect_filled(region, Rounding::ZERO, SELECTION.gamma_multiply(0.5));
+  for ms in [start, end].into_iter().flatten() {
+    let x = to_x(ms);
+    painter.line_segment(
+      [Pos2::new(x, *y_range.start() - 3.), Pos2::new(x, *y_range.end() + 3.)],
+      Stroke::new(2., SELECTION),
+    );
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Handle {
+  Start,
+  End,
+}
+
+pub struct Timeline {
+  dragging: Option<Handle>,
+  hovered: Option<i64>,
+  // 1 shows the whole file, the view starts at `view_start` ms
+  zoom: f32,
+  view_start: i64,
+  playhead_visible: bool,
+}
+
+impl Default for Timeline {
+  fn default() -> Self {
+    Self {
+      dragging: None,
+      hovered: None,
+      zoom: 1.,
+      view_start: 0,
+      playhead_visible: true,
+    }
+  }
+}
+
+impl Timeline {
+  fn span(&self, duration_ms: i64) -> i64 {
+    ((duration_ms as f64 / self.zoom as f64) as i64).max(1)
+  }
+
+  fn clamp_view(&mut self, duration_ms: i64) {
+    self.view_start = self.view_start.clamp(0, (duration_ms - self.span(duration_ms)).max(0));
+  }
+
+  // `around` stays put on screen, or gets centred if it was out of view
+  fn zoom_by(&mut self, factor: f32, around: i64, duration_ms: i64) {
+    let old_span = self.span(duration_ms) as f64;
+    let mut at = (around - self.view_start) as f64 / old_span;
+    if !(0. ..=1.).contains(&at) {
+      at = 0.5;
+    }
+    self.zoom = (self.zoom * factor).clamp(1., MAX_ZOOM);
+    self.view_start = around - (at * self.span(duration_ms) as f64) as i64;
+    self.clamp_view(duration_ms);
+  }
+
+  // Timestamp under the cursor, if it's over the timeline
+  pub fn hovered(&self) -> Option<i64> {
+    self.hovered
+  }
+
+  // Returns a timestamp to seek to when empty timeline space is clicked, keyframe
+  // ticks are left out when `keyframes` is None
+  #[allow(clippy::too_many_arguments)]
+  pub fn ui(
+    &mut self,
+    ui: &mut Ui,
+    duration_ms: i64,
+    elapsed_ms: i64,
+    start: &mut Option<i64>,
+    end: &mut Option<i64>,
+    segments: &[(i64, i64)],
+    markers: &[Marker],
+    keyframes: Option<&Keyframes>,
+  ) -> Option<i64> {
+    let size = Vec2::new(ui.available_width(), 24.);
+    let mut zoom = None;
+    ui.horizontal(|ui| {
+      if ui.small_button("-").clicked() {
+        zoom = Some(1. / ZOOM_STEP);
+      }
+      if ui.small_button("+").clicked() {
+        zoom = Some(ZOOM_STEP);
+      }
+      if ui.small_button("Fit").clicked() {
+        zoom = Some(0.);
+      }
+      if self.zoom > 1. {
+        ui.label(format!("{:.0}x, ctrl+scroll zooms and scroll pans", self.zoom));
+      } else {
+        ui.label("ctrl+scroll over the timeline to zoom");
+      }
+    });
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, Rounding::same(2.), Color32::from_gray(40));
+
+    self.hovered = None;
+    if duration_ms <= 0 {
+      return None;
+    }
+
+    if let Some(factor) = zoom {
+      self.zoom_by(factor, elapsed_ms, duration_ms);
+    }
+    if response.hovered() {
+      let (factor, scroll) = ui.input(|i| (i.zoom_delta(), i.scroll_delta));
+      if factor != 1. {
+        self.zoom_by(factor, elapsed_ms, duration_ms);
+      } else if self.zoom > 1. {
+        let delta = if scroll.x != 0. { scroll.x } else { scroll.y };
+        self.view_start -= (delta / rect.width() * self.span(duration_ms) as f32) as i64;
+      }
+    }
+    self.clamp_view(duration_ms);
+
+    let view_start = self.view_start;
+    let span = self.span(duration_ms);
+    // Pages along with playback once the playhead runs off the edge
+    let visible = (view_start..=view_start + span).contains(&elapsed_ms);
+    if self.playhead_visible && !visible && self.dragging.is_none() {
+      self.view_start = if elapsed_ms > view_start { elapsed_ms } else { elapsed_ms - span };
+      self.clamp_view(duration_ms);
+    }
+    let view_start = self.view_start;
+    self.playhead_visible = (view_start..=view_start + span).contains(&elapsed_ms);
+
+    let to_x = |ms: i64| rect.left() + rect.width() * ((ms - view_start) as f32 / span as f32).clamp(0., 1.);
+    let to_ms = |x: f32| view_start + (((x - rect.left()) / rect.width()).clamp(0., 1.) as f64 * span as f64).round() as i64;
+    let in_view = |ms: i64| (view_start..=view_start + span).contains(&ms);
+
+    self.hovered = response.hover_pos().map(|pos| to_ms(pos.x));
+
+    if response.drag_started() {
+      if let Some(pos) = response.interact_pointer_pos() {
+        let near = |ms: Option<i64>| ms.map(|ms| (to_x(ms) - pos.x).abs()).filter(|d| *d <= HANDLE_GRAB);
+        self.dragging = match (near(*start), near(*end)) {
+          (Some(s), Some(e)) => Some(if s <= e { Handle::Start } else { Handle::End }),
+          (Some(_), None) => Some(Handle::Start),
+          (None, Some(_)) => Some(Handle::End),
+          (None, None) => None,
+        };
+      }
+    }
+
+    let mut seek = None;
+    match (self.dragging, response.interact_pointer_pos()) {
+      (Some(handle), Some(pos)) if response.dragged() => {
+        let ms = to_ms(pos.x);
+        match handle {
+          Handle::Start => *start = Some(end.map_or(ms, |end| ms.min(end))),
+          Handle::End => *end = Some(start.map_or(ms, |start| ms.max(start))),
+        }
+      },
+      (None, Some(pos)) if response.clicked() => {
+        seek = Some(to_ms(pos.x));
+      },
+      _ => {},
+    }
+    if response.drag_released() {
+      self.dragging = None;
+    }
+
+    if let Some(keyframes) = keyframes {
+      // The keyframe a marker would snap to in copy mode stands out from the rest
+      let nearest: Vec<i64> = [*start, *end].into_iter().flatten().filter_map(|ms| keyframes.nearest(ms)).collect();
+      for ms in keyframes.between(view_start, view_start + span) {
+        let x = to_x(ms);
+        let (top, color) = if nearest.contains(&ms) {
+          (rect.top(), Color32::YELLOW)
+        } else {
+          (rect.bottom() - rect.height() / 3., Color32::from_gray(140))
+        };
+        painter.line_segment([Pos2::new(x, top), Pos2::new(x, rect.bottom())], Stroke::new(1., color));
+      }
+    }
+    for (from, to) in segments {
+      let region = Rect::from_x_y_ranges(to_x(*from)..=to_x(*to), rect.y_range());
+      painter.rect_filled(region, Rounding::ZERO, Color32::from_rgba_unmultiplied(100, 220, 120, 70));
+    }
+    if start.is_some() || end.is_some() {
+      let region = Rect::from_x_y_ranges(
+        to_x(start.unwrap_or(0))..=to_x(end.unwrap_or(duration_ms)),
+        rect.y_range(),
+      );
+      painter.rect_filled(region, Rounding::ZERO, Color32::from_rgba_unmultiplied(80, 160, 255, 60));
+    }
+    for ms in [*start, *end].into_iter().flatten().filter(|ms| in_view(*ms)) {
+      let x = to_x(ms);
+      painter.line_segment(
+        [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+        Stroke::new(3., SELECTION),
+      );
+    }
+
+    for marker in markers.iter().filter(|marker| in_view(marker.ms)) {
+      let x = to_x(marker.ms);
+      painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(1., MARKER));
+      painter.text(Pos2::new(x + 2., rect.top()), Align2::LEFT_TOP, &marker.label, FontId::proportional(10.), MARKER);
+    }
+
+    if in_view(elapsed_ms) {
+      let playhead = to_x(elapsed_ms);
+      painter.line_segment(
+        [Pos2::new(playhead, rect.top()), Pos2::new(playhead, rect.bottom())],
+        Stroke::new(2., Color32::WHITE),
+      );
+    }
+
+    seek
+  }
+}
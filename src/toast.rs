@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use egui::{Align2, Area, Color32, Context, Frame, Id, Order, Rounding};
+
+const LIFETIME: Duration = Duration::from_secs(4);
+// Toasts fade out over the end of their lifetime
+const FADE: Duration = Duration::from_millis(800);
+
+struct Toast {
+  text: String,
+  error: bool,
+  shown: Instant,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+  toasts: Vec<Toast>,
+}
+
+impl Toasts {
+  pub fn info(&mut self, text: impl Into<String>) {
+    self.toasts.push(Toast { text: text.into(), error: false, shown: Instant::now() });
+  }
+
+  pub fn error(&mut self, text: impl Into<String>) {
+    self.toasts.push(Toast { text: text.into(), error: true, shown: Instant::now() });
+  }
+
+  pub fn ui(&mut self, ctx: &Context) {
+    self.toasts.retain(|toast| toast.shown.elapsed() < LIFETIME);
+    if self.toasts.is_empty() {
+      return;
+    }
+    Area::new(Id::new("toasts"))
+      .anchor(Align2::RIGHT_BOTTOM, [-8., -8.])
+      .order(Order::Foreground)
+      .interactable(false)
+      .show(ctx, |ui| {
+        for toast in &self.toasts {
+          let left = LIFETIME.saturating_sub(toast.shown.elapsed());
+          let opacity = (left.as_secs_f32() / FADE.as_secs_f32()).min(1.);
+          let fill = if toast.error { Color32::from_rgb(140, 30, 30) } else { Color32::from_gray(50) };
+          Frame::none()
+            .fill(fill.gamma_multiply(opacity))
+            .rounding(Rounding::same(4.))
+            .inner_margin(8.)
+            .show(ui, |ui| {
+              ui.colored_label(Color32::WHITE.gamma_multiply(opacity), &toast.text);
+            });
+        }
+      });
+    ctx.request_repaint_after(Duration::from_millis(50));
+  }
+}
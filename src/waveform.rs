@@ -0,0 +1,170 @@
+use std::{
+  collections::hash_map::DefaultHasher,
+  fs,
+  hash::{Hash, Hasher},
+  io::Read,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+};
+
+use egui::{Align2, Color32, FontId, Pos2, Sense, Stroke, Ui, Vec2};
+
+const SAMPLE_RATE: usize = 8000;
+const PEAKS_PER_SEC: usize = 100;
+const SAMPLES_PER_PEAK: usize = SAMPLE_RATE / PEAKS_PER_SEC;
+
+#[derive(Default)]
+pub struct Waveform {
+  peaks: Arc<Mutex<Option<Vec<f32>>>>,
+  // Set if ffmpeg couldn't decode any audio, like for a file without an audio stream
+  failed: Arc<AtomicBool>,
+  cache: Option<PathBuf>,
+}
+
+fn cache_path(dir: &Path, file: &Path) -> Option<PathBuf> {
+  let meta = fs::metadata(file).ok()?;
+  let mut hasher = DefaultHasher::new();
+  file.hash(&mut hasher);
+  meta.len().hash(&mut hasher);
+  meta.modified().ok()?.hash(&mut hasher);
+  Some(dir.join(format!("snip-waveform-{:016x}.bin", hasher.finish())))
+}
+
+fn read_cache(path: &Path) -> Option<Vec<f32>> {
+  let bytes = fs::read(path).ok()?;
+  Some(bytes
+    .chunks_exact(4)
+    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    .collect())
+}
+
+fn write_cache(path: &Path, peaks: &[f32]) {
+  let bytes: Vec<u8> = peaks.iter().flat_map(|p| p.to_le_bytes()).collect();
+  let _ = fs::write(path, bytes);
+}
+
+fn extract_peaks(ffmpeg: &Path, file: &Path) -> Option<Vec<f32>> {
+  let mut child = Command::new(ffmpeg)
+    .arg("-i")
+    .arg(file)
+    .args(["-vn", "-ac", "1", "-ar", &SAMPLE_RATE.to_string(), "-f", "s16le", "-"])
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .ok()?;
+
+  let mut peaks = vec![];
+  if let Some(mut stdout) = child.stdout.take() {
+    let mut buf = [0u8; SAMPLES_PER_PEAK * 2];
+    let mut filled = 0;
+    loop {
+      match stdout.read(&mut buf[filled..]) {
+        Ok(0) | Err(_) => break,
+        Ok(n) => filled += n,
+      }
+      if filled == buf.len() {
+        let peak = buf
+          .chunks_exact(2)
+          .map(|b| (i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32).abs())
+          .fold(0., f32::max);
+        peaks.push(peak);
+        filled = 0;
+      }
+    }
+  }
+  child.wait().ok().filter(|s| s.success())?;
+  Some(peaks)
+}
+
+impl Waveform {
+  // The peaks are cached in `dir` so reopening a file is instant, until snip quits
+  pub fn load(ffmpeg: &Path, file: &Path, dir: &Path) -> Self {
+    let peaks = Arc::new(Mutex::new(None));
+    let failed = Arc::new(AtomicBool::new(false));
+    let cache = cache_path(dir, file);
+    match cache.as_deref().and_then(read_cache) {
+      Some(cached) => *peaks.lock().unwrap() = Some(cached),
+      None => {
+        let peaks = peaks.clone();
+        let failed = failed.clone();
+        let cache = cache.clone();
+        let ffmpeg = ffmpeg.to_path_buf();
+        let file = file.to_path_buf();
+        std::thread::spawn(move || {
+          match extract_peaks(&ffmpeg, &file) {
+            Some(extracted) => {
+              if let Some(cache) = cache {
+                write_cache(&cache, &extracted);
+              }
+              *peaks.lock().unwrap() = Some(extracted);
+            },
+            None => failed.store(true, Ordering::Relaxed),
+          }
+        });
+      },
+    }
+    Self { peaks, failed, cache }
+  }
+
+  pub fn cache_file(&self) -> Option<&Path> {
+    self.cache.as_deref()
+  }
+
+  // Returns the clicked timestamp in ms
+  pub fn ui(&self, ui: &mut Ui, elapsed_ms: i64, duration_ms: i64) -> Option<i64> {
+    let size = Vec2::new(ui.available_width(), 60.);
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0., Color32::from_gray(24));
+
+    if duration_ms <= 0 {
+      return None;
+    }
+
+    let peaks = self.peaks.lock().unwrap();
+    if let Some(peaks) = peaks.as_ref().filter(|p| !p.is_empty()) {
+      let mid = rect.center().y;
+      let columns = rect.width().max(1.) as usize;
+      let total = duration_ms as f32 / 1000. * PEAKS_PER_SEC as f32;
+      for x in 0..columns {
+        let from = (x as f32 / columns as f32 * total) as usize;
+        let to = (((x + 1) as f32 / columns as f32 * total) as usize).max(from + 1);
+        let peak = peaks
+          .get(from.min(peaks.len())..to.min(peaks.len()))
+          .map(|p| p.iter().copied().fold(0., f32::max))
+          .unwrap_or(0.);
+        let h = peak * rect.height() * 0.5;
+        let px = rect.left() + x as f32;
+        painter.line_segment(
+          [Pos2::new(px, mid - h), Pos2::new(px, mid + h)],
+          Stroke::new(1., Color32::LIGHT_BLUE),
+        );
+      }
+    } else {
+      let no_audio = peaks.is_some() || self.failed.load(Ordering::Relaxed);
+      painter.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        if no_audio { "no audio" } else { "loading waveform..." },
+        FontId::default(),
+        Color32::GRAY,
+      );
+    }
+
+    let playhead = rect.left() + rect.width() * (elapsed_ms as f32 / duration_ms as f32).clamp(0., 1.);
+    painter.line_segment(
+      [Pos2::new(playhead, rect.top()), Pos2::new(playhead, rect.bottom())],
+      Stroke::new(2., Color32::WHITE),
+    );
+
+    if response.clicked() {
+      if let Some(pos) = response.interact_pointer_pos() {
+        let frac = ((pos.x - rect.left()) / rect.width()).clamp(0., 1.);
+        return Some((frac as f64 * duration_ms as f64) as i64);
+      }
+    }
+    None
+  }
+}